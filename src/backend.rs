@@ -0,0 +1,411 @@
+use crate::colors::ColorDepth;
+use crate::frame_buffer::{Cell, Style};
+use crate::position::{Position, PositionTrait};
+use crate::unicode;
+use std::io::{self, Stdout, Write};
+use termion::raw::{IntoRawMode, RawTerminal};
+use termion::{clear, cursor, style, terminal_size};
+
+/// Output to which [FrameBuffer] is flushed
+///
+/// Positions are 1-based, same as in terminal
+pub trait Backend {
+    /// Size of the output
+    fn size(&self) -> Position;
+    /// Change size of the output, e.g. after terminal was resized
+    fn resize(&mut self, size: Position);
+    /// Set [Style] used by following [Backend::put_cell] calls
+    fn set_style(&mut self, style: &Style) -> io::Result<()>;
+    /// Put `symbol` (single grapheme) in `position` using current [Style], wide symbol covers also the next cell
+    fn put_cell(&mut self, position: &Position, symbol: &str) -> io::Result<()>;
+    /// Clear whole output
+    fn clear(&mut self) -> io::Result<()>;
+    /// Flush everything written so far, called once per frame
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// [Backend] writing ANSI escape codes using termion
+pub struct TermionBackend<W: Write> {
+    /// Where escape codes are written
+    out: W,
+    /// Size of the output
+    size: Position,
+    /// [Position] where cursor will be after last write, [None] if not known
+    cursor: Option<Position>,
+    /// [Style] currently set, [None] if not known
+    style: Option<Style>,
+    /// [ColorDepth] of the terminal, colors are downgraded to it
+    color_depth: ColorDepth,
+}
+
+impl<W: Write> TermionBackend<W> {
+    /// Create new [TermionBackend] writing to `out`
+    pub fn new(out: W, size: Position) -> Self {
+        Self {
+            out,
+            size,
+            cursor: None,
+            style: None,
+            color_depth: ColorDepth::TrueColor,
+        }
+    }
+
+    /// Same [TermionBackend] displaying colors using `color_depth`, see [Color::downgrade]
+    pub fn with_color_depth(mut self, color_depth: ColorDepth) -> Self {
+        self.color_depth = color_depth;
+        self
+    }
+
+    /// Create new [TermionBackend] writing to `out` and prepare it for drawing: clear it and hide cursor
+    ///
+    /// Cursor is shown again when backend is dropped
+    pub fn init(mut out: W, size: Position) -> io::Result<Self> {
+        write!(out, "{}{}{}", clear::All, cursor::Hide, style::Reset)?;
+        out.flush()?;
+        Ok(Self::new(out, size))
+    }
+
+    /// Get reference to underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+}
+
+impl TermionBackend<RawTerminal<Stdout>> {
+    /// Create [TermionBackend] for stdout, switching terminal to raw mode
+    ///
+    /// Terminal is restored when backend is dropped
+    pub fn stdout() -> io::Result<Self> {
+        let (x, y) = terminal_size()?;
+        Self::init(io::stdout().into_raw_mode()?, Position::new(x, y))
+    }
+}
+
+impl<W: Write> Backend for TermionBackend<W> {
+    fn size(&self) -> Position {
+        self.size
+    }
+
+    fn resize(&mut self, size: Position) {
+        self.size = size;
+        self.cursor = None;
+    }
+
+    fn set_style(&mut self, style: &Style) -> io::Result<()> {
+        if self.style.as_ref() == Some(style) {
+            return Ok(());
+        }
+        write!(
+            self.out,
+            "{}",
+            style.downgrade(self.color_depth).get_ansi_string()
+        )?;
+        self.style = Some(style.clone());
+        Ok(())
+    }
+
+    fn put_cell(&mut self, position: &Position, symbol: &str) -> io::Result<()> {
+        if self.cursor != Some(*position) {
+            write!(self.out, "{}", cursor::Goto(position.x(), position.y()))?;
+        }
+        write!(self.out, "{symbol}")?;
+        let next_x = position.x() + unicode::grapheme_width(symbol) as u16;
+        // cursor does not wrap to next line after writing to last column
+        self.cursor = if next_x <= self.size.x() {
+            Some(Position::new(next_x, position.y()))
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        write!(self.out, "{}{}", style::Reset, clear::All)?;
+        self.style = Some(Style::default());
+        self.cursor = None;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Drop for TermionBackend<W> {
+    fn drop(&mut self) {
+        // errors are ignored, there is nothing sensible to do with them while dropping
+        let _ = write!(
+            self.out,
+            "{}{}{}{}",
+            style::Reset,
+            clear::All,
+            cursor::Show,
+            cursor::Goto(1, 1)
+        );
+        let _ = self.out.flush();
+    }
+}
+
+/// [Backend] which keeps [Cell]s in memory, e.g. for testing what was rendered
+pub struct MemoryBackend {
+    /// Size of the output
+    size: Position,
+    /// Cells, row by row
+    cells: Vec<Cell>,
+    /// [Style] currently set
+    style: Style,
+    /// Number of [Backend::flush] calls so far
+    frames: usize,
+}
+
+impl MemoryBackend {
+    /// Create new empty [MemoryBackend] of `size`
+    pub fn new(size: Position) -> Self {
+        Self {
+            size,
+            cells: vec![Cell::default(); usize::from(size.x()) * usize::from(size.y())],
+            style: Style::default(),
+            frames: 0,
+        }
+    }
+
+    /// Index of `position` in `cells` or [None] if `position` is outside of the output
+    fn index(&self, position: &Position) -> Option<usize> {
+        if position.x() == 0 || position.y() == 0 || position.is_out_of_bounds(&self.size) {
+            return None;
+        }
+        Some(
+            usize::from(position.y() - 1) * usize::from(self.size.x())
+                + usize::from(position.x() - 1),
+        )
+    }
+
+    /// Get [Cell] in `position`, [None] if `position` is outside of the output
+    pub fn cell(&self, position: &Position) -> Option<&Cell> {
+        self.index(position).map(|index| &self.cells[index])
+    }
+
+    /// Characters of the output as text, one line per row
+    pub fn text(&self) -> String {
+        self.cells
+            .chunks(usize::from(self.size.x()).max(1))
+            .map(|row| row.iter().map(|cell| cell.symbol.as_str()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Output as text with ANSI escape codes for styles, one line per row
+    ///
+    /// Each line starts and ends with reset style, so lines can be printed independently
+    pub fn ansi_text(&self) -> String {
+        self.cells
+            .chunks(usize::from(self.size.x()).max(1))
+            .map(|row| {
+                let mut line = String::new();
+                let mut current_style = None;
+                for cell in row {
+                    if current_style != Some(&cell.style) {
+                        line.push_str(&cell.style.get_ansi_string());
+                        current_style = Some(&cell.style);
+                    }
+                    line.push_str(&cell.symbol);
+                }
+                line.push_str(style::Reset.as_ref());
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Number of flushed frames
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn size(&self) -> Position {
+        self.size
+    }
+
+    fn resize(&mut self, size: Position) {
+        *self = Self {
+            frames: self.frames,
+            ..Self::new(size)
+        };
+    }
+
+    fn set_style(&mut self, style: &Style) -> io::Result<()> {
+        self.style = style.clone();
+        Ok(())
+    }
+
+    fn put_cell(&mut self, position: &Position, symbol: &str) -> io::Result<()> {
+        if let Some(index) = self.index(position) {
+            self.cells[index] = Cell {
+                symbol: symbol.to_owned(),
+                style: self.style.clone(),
+            };
+            if self.cells[index].is_wide() && position.x() < self.size.x() {
+                self.cells[index + 1] = Cell {
+                    symbol: String::new(),
+                    style: self.style.clone(),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.cells.fill(Cell::default());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// [Backend] which puts everything to both of its backends, e.g. to display and record at the same time
+///
+/// [Backend::size] is taken from the first one
+pub struct TeeBackend<'a>(pub &'a mut dyn Backend, pub &'a mut dyn Backend);
+
+impl Backend for TeeBackend<'_> {
+    fn size(&self) -> Position {
+        self.0.size()
+    }
+
+    fn resize(&mut self, size: Position) {
+        self.0.resize(size);
+        self.1.resize(size);
+    }
+
+    fn set_style(&mut self, style: &Style) -> io::Result<()> {
+        self.0.set_style(style)?;
+        self.1.set_style(style)
+    }
+
+    fn put_cell(&mut self, position: &Position, symbol: &str) -> io::Result<()> {
+        self.0.put_cell(position, symbol)?;
+        self.1.put_cell(position, symbol)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.0.clear()?;
+        self.1.clear()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()?;
+        self.1.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::colors::Color;
+
+    #[test]
+    fn termion_coalesces_cursor_and_style() {
+        let mut backend = TermionBackend::new(Vec::new(), Position::new(3, 2));
+        let style = Style {
+            color: Some(Color::Palette(1)),
+            bold: false,
+            background: None,
+        };
+        backend.set_style(&style).unwrap();
+        backend.put_cell(&Position::new(2, 1), "a").unwrap();
+        backend.set_style(&style).unwrap();
+        backend.put_cell(&Position::new(3, 1), "b").unwrap();
+        backend.put_cell(&Position::new(1, 2), "c").unwrap();
+        let output = String::from_utf8(backend.get_ref().clone()).unwrap();
+        assert_eq!(
+            output,
+            format!(
+                "{}{}{}ab{}c",
+                style::Reset,
+                Color::Palette(1).get_ansi_string(),
+                cursor::Goto(2, 1),
+                cursor::Goto(1, 2),
+            )
+        );
+    }
+
+    #[test]
+    fn termion_cursor_after_wide_symbol() {
+        let mut backend = TermionBackend::new(Vec::new(), Position::new(4, 1));
+        backend.put_cell(&Position::new(1, 1), "ア").unwrap();
+        backend.put_cell(&Position::new(3, 1), "b").unwrap();
+        let output = String::from_utf8(backend.get_ref().clone()).unwrap();
+        assert_eq!(output, format!("{}アb", cursor::Goto(1, 1)));
+    }
+
+    #[test]
+    fn termion_downgrades_colors() {
+        let mut backend = TermionBackend::new(Vec::new(), Position::new(3, 2))
+            .with_color_depth(ColorDepth::Ansi256);
+        let style = Style {
+            color: Some(Color::RGB { r: 255, g: 0, b: 0 }),
+            bold: false,
+            background: None,
+        };
+        backend.set_style(&style).unwrap();
+        let output = String::from_utf8(backend.get_ref().clone()).unwrap();
+        assert_eq!(
+            output,
+            format!("{}{}", style::Reset, Color::Ansi256(196).get_ansi_string())
+        );
+    }
+
+    #[test]
+    fn memory_backend_text() {
+        let mut backend = MemoryBackend::new(Position::new(3, 2));
+        backend.put_cell(&Position::new(1, 1), "a").unwrap();
+        backend.put_cell(&Position::new(3, 2), "b").unwrap();
+        backend.put_cell(&Position::new(4, 2), "c").unwrap();
+        backend.flush().unwrap();
+        assert_eq!(backend.text(), "a  \n  b");
+        assert_eq!(backend.frames(), 1);
+        assert_eq!(backend.cell(&Position::new(1, 1)).unwrap().symbol, "a");
+        assert!(backend.cell(&Position::new(4, 1)).is_none());
+
+        backend.clear().unwrap();
+        assert_eq!(backend.text(), "   \n   ");
+    }
+
+    #[test]
+    fn tee_backend() {
+        let mut memory = MemoryBackend::new(Position::new(3, 1));
+        let mut termion = TermionBackend::new(Vec::new(), Position::new(3, 1));
+        let mut tee = TeeBackend(&mut memory, &mut termion);
+        tee.put_cell(&Position::new(2, 1), "a").unwrap();
+        tee.flush().unwrap();
+        assert_eq!(memory.text(), " a ");
+        assert_eq!(termion.get_ref(), format!("{}a", cursor::Goto(2, 1)).as_bytes());
+    }
+
+    #[test]
+    fn memory_backend_ansi_text() {
+        let mut backend = MemoryBackend::new(Position::new(3, 1));
+        let style = Style {
+            color: Some(Color::Palette(1)),
+            bold: false,
+            background: None,
+        };
+        backend.set_style(&style).unwrap();
+        backend.put_cell(&Position::new(2, 1), "a").unwrap();
+        backend.put_cell(&Position::new(3, 1), "b").unwrap();
+        assert_eq!(
+            backend.ansi_text(),
+            format!(
+                "{} {}ab{}",
+                Style::default().get_ansi_string(),
+                style.get_ansi_string(),
+                style::Reset
+            )
+        );
+    }
+}
@@ -0,0 +1,252 @@
+// Glyphs are from public domain `6x13` font of the X.Org project (misc-misc fonts)
+// Only printable ASCII is included, any other character is drawn as replacement glyph
+
+/// Width of glyph in pixels
+pub const GLYPH_WIDTH: usize = 6;
+/// Height of glyph in pixels
+pub const GLYPH_HEIGHT: usize = 13;
+
+/// Bitmap of a glyph, one [u8] per row, bit 5 is the leftmost pixel
+pub type Glyph = [u8; GLYPH_HEIGHT];
+
+/// Glyphs of printable ASCII characters, from `' '` to `'~'`
+const GLYPHS: [Glyph; 95] = [
+    // ' '
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '!'
+    [0x00, 0x00, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x08, 0x00, 0x00],
+    // '"'
+    [0x00, 0x00, 0x14, 0x14, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '#'
+    [0x00, 0x00, 0x00, 0x14, 0x14, 0x3e, 0x14, 0x3e, 0x14, 0x14, 0x00, 0x00, 0x00],
+    // '$'
+    [0x00, 0x00, 0x08, 0x1e, 0x28, 0x28, 0x1c, 0x0a, 0x0a, 0x3c, 0x08, 0x00, 0x00],
+    // '%'
+    [0x00, 0x00, 0x12, 0x2a, 0x14, 0x04, 0x08, 0x10, 0x14, 0x2a, 0x24, 0x00, 0x00],
+    // '&'
+    [0x00, 0x00, 0x00, 0x10, 0x28, 0x28, 0x10, 0x28, 0x26, 0x24, 0x1a, 0x00, 0x00],
+    // "'"
+    [0x00, 0x00, 0x08, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '('
+    [0x00, 0x04, 0x08, 0x08, 0x10, 0x10, 0x10, 0x10, 0x10, 0x08, 0x08, 0x04, 0x00],
+    // ')'
+    [0x00, 0x10, 0x08, 0x08, 0x04, 0x04, 0x04, 0x04, 0x04, 0x08, 0x08, 0x10, 0x00],
+    // '*'
+    [0x00, 0x00, 0x08, 0x2a, 0x1c, 0x2a, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x08, 0x3e, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00],
+    // ','
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x08, 0x10, 0x00],
+    // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '.'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x1c, 0x08, 0x00],
+    // '/'
+    [0x00, 0x00, 0x02, 0x02, 0x04, 0x04, 0x08, 0x10, 0x10, 0x20, 0x20, 0x00, 0x00],
+    // '0'
+    [0x00, 0x00, 0x08, 0x14, 0x22, 0x22, 0x22, 0x22, 0x22, 0x14, 0x08, 0x00, 0x00],
+    // '1'
+    [0x00, 0x00, 0x08, 0x18, 0x28, 0x08, 0x08, 0x08, 0x08, 0x08, 0x3e, 0x00, 0x00],
+    // '2'
+    [0x00, 0x00, 0x1c, 0x22, 0x22, 0x02, 0x04, 0x08, 0x10, 0x20, 0x3e, 0x00, 0x00],
+    // '3'
+    [0x00, 0x00, 0x3e, 0x02, 0x04, 0x08, 0x1c, 0x02, 0x02, 0x22, 0x1c, 0x00, 0x00],
+    // '4'
+    [0x00, 0x00, 0x04, 0x04, 0x0c, 0x14, 0x14, 0x24, 0x3e, 0x04, 0x04, 0x00, 0x00],
+    // '5'
+    [0x00, 0x00, 0x3e, 0x20, 0x20, 0x2c, 0x32, 0x02, 0x02, 0x22, 0x1c, 0x00, 0x00],
+    // '6'
+    [0x00, 0x00, 0x1c, 0x22, 0x20, 0x20, 0x3c, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00],
+    // '7'
+    [0x00, 0x00, 0x3e, 0x02, 0x04, 0x04, 0x08, 0x08, 0x10, 0x10, 0x10, 0x00, 0x00],
+    // '8'
+    [0x00, 0x00, 0x1c, 0x22, 0x22, 0x22, 0x1c, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00],
+    // '9'
+    [0x00, 0x00, 0x1c, 0x22, 0x22, 0x22, 0x1e, 0x02, 0x02, 0x22, 0x1c, 0x00, 0x00],
+    // ':'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x1c, 0x08, 0x00, 0x00, 0x08, 0x1c, 0x08, 0x00],
+    // ';'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x1c, 0x08, 0x00, 0x00, 0x0c, 0x08, 0x10, 0x00],
+    // '<'
+    [0x00, 0x00, 0x02, 0x04, 0x08, 0x10, 0x20, 0x10, 0x08, 0x04, 0x02, 0x00, 0x00],
+    // '='
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3e, 0x00, 0x00, 0x3e, 0x00, 0x00, 0x00, 0x00],
+    // '>'
+    [0x00, 0x00, 0x20, 0x10, 0x08, 0x04, 0x02, 0x04, 0x08, 0x10, 0x20, 0x00, 0x00],
+    // '?'
+    [0x00, 0x00, 0x1c, 0x22, 0x22, 0x02, 0x04, 0x08, 0x08, 0x00, 0x08, 0x00, 0x00],
+    // '@'
+    [0x00, 0x00, 0x1c, 0x22, 0x22, 0x26, 0x2a, 0x2a, 0x2c, 0x20, 0x1e, 0x00, 0x00],
+    // 'A'
+    [0x00, 0x00, 0x08, 0x14, 0x22, 0x22, 0x22, 0x3e, 0x22, 0x22, 0x22, 0x00, 0x00],
+    // 'B'
+    [0x00, 0x00, 0x3c, 0x12, 0x12, 0x12, 0x1c, 0x12, 0x12, 0x12, 0x3c, 0x00, 0x00],
+    // 'C'
+    [0x00, 0x00, 0x1c, 0x22, 0x20, 0x20, 0x20, 0x20, 0x20, 0x22, 0x1c, 0x00, 0x00],
+    // 'D'
+    [0x00, 0x00, 0x3c, 0x12, 0x12, 0x12, 0x12, 0x12, 0x12, 0x12, 0x3c, 0x00, 0x00],
+    // 'E'
+    [0x00, 0x00, 0x3e, 0x20, 0x20, 0x20, 0x3c, 0x20, 0x20, 0x20, 0x3e, 0x00, 0x00],
+    // 'F'
+    [0x00, 0x00, 0x3e, 0x20, 0x20, 0x20, 0x3c, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00],
+    // 'G'
+    [0x00, 0x00, 0x1c, 0x22, 0x20, 0x20, 0x20, 0x26, 0x22, 0x22, 0x1c, 0x00, 0x00],
+    // 'H'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x22, 0x3e, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00],
+    // 'I'
+    [0x00, 0x00, 0x1c, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x1c, 0x00, 0x00],
+    // 'J'
+    [0x00, 0x00, 0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x24, 0x18, 0x00, 0x00],
+    // 'K'
+    [0x00, 0x00, 0x22, 0x22, 0x24, 0x28, 0x30, 0x28, 0x24, 0x22, 0x22, 0x00, 0x00],
+    // 'L'
+    [0x00, 0x00, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x3e, 0x00, 0x00],
+    // 'M'
+    [0x00, 0x00, 0x22, 0x22, 0x36, 0x2a, 0x2a, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00],
+    // 'N'
+    [0x00, 0x00, 0x22, 0x32, 0x32, 0x2a, 0x2a, 0x26, 0x26, 0x22, 0x22, 0x00, 0x00],
+    // 'O'
+    [0x00, 0x00, 0x1c, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00],
+    // 'P'
+    [0x00, 0x00, 0x3c, 0x22, 0x22, 0x22, 0x3c, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00],
+    // 'Q'
+    [0x00, 0x00, 0x1c, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x2a, 0x1c, 0x02, 0x00],
+    // 'R'
+    [0x00, 0x00, 0x3c, 0x22, 0x22, 0x22, 0x3c, 0x28, 0x24, 0x22, 0x22, 0x00, 0x00],
+    // 'S'
+    [0x00, 0x00, 0x1c, 0x22, 0x20, 0x20, 0x1c, 0x02, 0x02, 0x22, 0x1c, 0x00, 0x00],
+    // 'T'
+    [0x00, 0x00, 0x3e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x00],
+    // 'U'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00],
+    // 'V'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x22, 0x14, 0x14, 0x14, 0x08, 0x08, 0x00, 0x00],
+    // 'W'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x22, 0x2a, 0x2a, 0x2a, 0x2a, 0x14, 0x00, 0x00],
+    // 'X'
+    [0x00, 0x00, 0x22, 0x22, 0x14, 0x14, 0x08, 0x14, 0x14, 0x22, 0x22, 0x00, 0x00],
+    // 'Y'
+    [0x00, 0x00, 0x22, 0x22, 0x14, 0x14, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x00],
+    // 'Z'
+    [0x00, 0x00, 0x3e, 0x02, 0x04, 0x04, 0x08, 0x10, 0x10, 0x20, 0x3e, 0x00, 0x00],
+    // '['
+    [0x00, 0x1c, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1c, 0x00],
+    // '\\'
+    [0x00, 0x00, 0x20, 0x20, 0x10, 0x10, 0x08, 0x04, 0x04, 0x02, 0x02, 0x00, 0x00],
+    // ']'
+    [0x00, 0x1c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x1c, 0x00],
+    // '^'
+    [0x00, 0x00, 0x08, 0x14, 0x22, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '_'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3e, 0x00],
+    // '`'
+    [0x00, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'a'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x02, 0x1e, 0x22, 0x26, 0x1a, 0x00, 0x00],
+    // 'b'
+    [0x00, 0x00, 0x20, 0x20, 0x20, 0x3c, 0x22, 0x22, 0x22, 0x22, 0x3c, 0x00, 0x00],
+    // 'c'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x22, 0x20, 0x20, 0x22, 0x1c, 0x00, 0x00],
+    // 'd'
+    [0x00, 0x00, 0x02, 0x02, 0x02, 0x1e, 0x22, 0x22, 0x22, 0x22, 0x1e, 0x00, 0x00],
+    // 'e'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x22, 0x3e, 0x20, 0x22, 0x1c, 0x00, 0x00],
+    // 'f'
+    [0x00, 0x00, 0x0c, 0x12, 0x10, 0x10, 0x3c, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00],
+    // 'g'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x22, 0x22, 0x22, 0x1e, 0x02, 0x22, 0x1c],
+    // 'h'
+    [0x00, 0x00, 0x20, 0x20, 0x20, 0x2c, 0x32, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00],
+    // 'i'
+    [0x00, 0x00, 0x00, 0x08, 0x00, 0x18, 0x08, 0x08, 0x08, 0x08, 0x1c, 0x00, 0x00],
+    // 'j'
+    [0x00, 0x00, 0x00, 0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x24, 0x24, 0x18],
+    // 'k'
+    [0x00, 0x00, 0x20, 0x20, 0x20, 0x24, 0x28, 0x30, 0x28, 0x24, 0x22, 0x00, 0x00],
+    // 'l'
+    [0x00, 0x00, 0x18, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x1c, 0x00, 0x00],
+    // 'm'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0x2a, 0x2a, 0x2a, 0x2a, 0x22, 0x00, 0x00],
+    // 'n'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x32, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00],
+    // 'o'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x22, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00],
+    // 'p'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x22, 0x22, 0x22, 0x3c, 0x20, 0x20, 0x20],
+    // 'q'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1e, 0x22, 0x22, 0x22, 0x1e, 0x02, 0x02, 0x02],
+    // 'r'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x32, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00],
+    // 's'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x22, 0x18, 0x04, 0x22, 0x1c, 0x00, 0x00],
+    // 't'
+    [0x00, 0x00, 0x00, 0x10, 0x10, 0x3c, 0x10, 0x10, 0x10, 0x12, 0x0c, 0x00, 0x00],
+    // 'u'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x22, 0x22, 0x22, 0x26, 0x1a, 0x00, 0x00],
+    // 'v'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x22, 0x22, 0x14, 0x14, 0x08, 0x00, 0x00],
+    // 'w'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x22, 0x2a, 0x2a, 0x2a, 0x14, 0x00, 0x00],
+    // 'x'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x14, 0x08, 0x08, 0x14, 0x22, 0x00, 0x00],
+    // 'y'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x22, 0x22, 0x26, 0x1a, 0x02, 0x22, 0x1c],
+    // 'z'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3e, 0x04, 0x08, 0x10, 0x20, 0x3e, 0x00, 0x00],
+    // '{'
+    [0x00, 0x06, 0x08, 0x08, 0x08, 0x08, 0x30, 0x08, 0x08, 0x08, 0x08, 0x06, 0x00],
+    // '|'
+    [0x00, 0x00, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x00],
+    // '}'
+    [0x00, 0x30, 0x08, 0x08, 0x08, 0x08, 0x06, 0x08, 0x08, 0x08, 0x08, 0x30, 0x00],
+    // '~'
+    [0x00, 0x00, 0x12, 0x2a, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+];
+
+/// Glyph used for characters which are not in the font
+const REPLACEMENT: Glyph = [
+    0x00, 0x00, 0x1c, 0x22, 0x22, 0x02, 0x04, 0x08, 0x08, 0x00, 0x08, 0x00, 0x00,
+];
+
+/// Does the font have [Glyph] of `ch`, only printable ASCII characters are included
+pub fn has_glyph(ch: char) -> bool {
+    matches!(ch, ' '..='~')
+}
+
+/// Get [Glyph] of `ch`, characters missing in the font get replacement glyph
+pub fn glyph(ch: char) -> &'static Glyph {
+    if has_glyph(ch) {
+        &GLYPHS[ch as usize - ' ' as usize]
+    } else {
+        &REPLACEMENT
+    }
+}
+
+/// Is pixel in column `x` and `row` of the `glyph` set?
+pub fn is_set(glyph: &Glyph, x: usize, row: usize) -> bool {
+    glyph[row] >> (GLYPH_WIDTH - 1 - x) & 1 == 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glyph_pixels() {
+        let a = glyph('A');
+        // top of 'A' is one pixel in the middle
+        assert!(is_set(a, 2, 2));
+        assert!(!is_set(a, 1, 2));
+        // bar of 'A' spans 5 pixels
+        assert!((0..5).all(|x| is_set(a, x, 7)));
+        assert!(!is_set(a, 5, 7));
+        assert!(glyph(' ').iter().all(|&row| row == 0));
+    }
+
+    #[test]
+    fn missing_glyph_is_replaced() {
+        assert!(!has_glyph('\u{30a2}'));
+        assert_eq!(glyph('\u{30a2}'), &REPLACEMENT);
+        assert_ne!(glyph('?'), glyph(' '));
+    }
+}
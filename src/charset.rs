@@ -0,0 +1,134 @@
+use crate::error::Error;
+use crate::unicode;
+use std::collections::HashSet;
+use std::fs;
+
+/// Names of built-in charsets, see [builtin]
+pub const BUILTIN_CHARSETS: [&str; 10] = [
+    "default",
+    "katakana",
+    "binary",
+    "hex",
+    "digits",
+    "braille",
+    "runic",
+    "box-drawing",
+    "greek",
+    "emoji",
+];
+
+/// Characters of the default charset, kept exactly as they always were so that seeded runs look the same
+const DEFAULT_CHARS: &str =
+    "abcdefghijklmnopqrstuwvxyzABCDEFGHIJKLMNOPQRSTUWVXYZ0123456789!@$%^&*()_+|{}[]<>?!~\\/.,:;";
+
+/// Characters from `first` to `last` (inclusive) code points, invalid code points and characters which take
+/// no space in terminal (control characters, combining marks) are skipped
+fn range(first: u32, last: u32) -> impl Iterator<Item = char> {
+    (first..=last)
+        .filter_map(char::from_u32)
+        .filter(|&ch| unicode::char_width(ch) > 0)
+}
+
+/// Get characters of built-in charset with `name`, see [BUILTIN_CHARSETS]
+pub fn builtin(name: &str) -> Option<String> {
+    let chars = match name {
+        "default" => DEFAULT_CHARS.into(),
+        // half-width katakana, like in the film
+        "katakana" => range(0xFF66, 0xFF9D).collect(),
+        "binary" => "01".into(),
+        "hex" => "0123456789ABCDEF".into(),
+        "digits" => "0123456789".into(),
+        // without empty pattern, which would look like missing character
+        "braille" => range(0x2801, 0x28FF).collect(),
+        "runic" => range(0x16A0, 0x16EA).collect(),
+        "box-drawing" => range(0x2500, 0x257F).collect(),
+        // U+03A2 is unassigned
+        "greek" => range(0x0391, 0x03A1)
+            .chain(range(0x03A3, 0x03A9))
+            .chain(range(0x03B1, 0x03C9))
+            .collect(),
+        "emoji" => range(0x1F600, 0x1F64F).collect(),
+        _ => return None,
+    };
+    Some(chars)
+}
+
+/// Parse `U+XXXX` code point
+fn code_point(text: &str) -> Option<u32> {
+    let hex = text.trim().strip_prefix("U+").or_else(|| text.trim().strip_prefix("u+"))?;
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Parse charset `spec` into characters to use
+///
+/// `spec` is comma separated list of built-in charset names (see [BUILTIN_CHARSETS]), Unicode ranges
+/// (`U+30A0-U+30FF`), single code points (`U+2603`) and `file:<path>` with characters to use.
+/// Characters which appear more than once are used only once.
+pub fn parse(spec: &str) -> Result<String, Error> {
+    let mut chars = String::new();
+    let mut seen = HashSet::new();
+    for item in spec.split(',').map(str::trim) {
+        let part = if let Some(path) = item.strip_prefix("file:") {
+            let text = fs::read_to_string(path).map_err(|e| {
+                Error::ConfigParse(format!("cannot read charset file '{path}': {e}"))
+            })?;
+            text.chars().filter(|ch| !ch.is_control()).collect()
+        } else if let Some(chars) = builtin(item) {
+            chars
+        } else {
+            let (first, last) = item.split_once('-').unwrap_or((item, item));
+            match (code_point(first), code_point(last)) {
+                (Some(first), Some(last)) if first <= last => range(first, last).collect(),
+                _ => {
+                    return Err(Error::ConfigParse(format!(
+                        "unknown charset '{item}', use one of {}, U+XXXX-U+XXXX range or file:<path>",
+                        BUILTIN_CHARSETS.join(", ")
+                    )))
+                }
+            }
+        };
+        for grapheme in unicode::graphemes(&part) {
+            if seen.insert(grapheme.to_owned()) {
+                chars.push_str(grapheme);
+            }
+        }
+    }
+    if chars.is_empty() {
+        return Err(Error::ConfigParse(format!("charset '{spec}' has no characters")));
+    }
+    Ok(chars)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn builtin_charsets() {
+        for name in BUILTIN_CHARSETS {
+            assert!(!builtin(name).unwrap().is_empty(), "{name}");
+        }
+        assert_eq!(builtin("katakana").unwrap().chars().next(), Some('ｦ'));
+        assert_eq!(builtin("greek").unwrap().chars().count(), 49);
+        assert!(builtin("klingon").is_none());
+    }
+
+    #[test]
+    fn parse_combines_sets_and_ranges() {
+        assert_eq!(parse("binary, digits").unwrap(), "0123456789");
+        assert_eq!(parse("U+30A2-U+30A4,U+41").unwrap(), "アィイA");
+        assert!(parse("U+30A4-U+30A2").is_err());
+        assert!(parse("klingon").is_err());
+        assert!(parse("U+0300-U+0301").is_err());
+    }
+
+    #[test]
+    fn parse_file() {
+        let path = env::temp_dir().join(format!("rumatrix-charset-{}.txt", std::process::id()));
+        fs::write(&path, "ab\nba\n").unwrap();
+        assert_eq!(parse(&format!("file:{},hex", path.display())).unwrap(), "ab0123456789ABCDEF");
+        fs::remove_file(&path).unwrap();
+        assert!(parse("file:/nonexistent/charset.txt").is_err());
+    }
+}
@@ -1,8 +1,17 @@
-use serde::{Deserialize, Serialize};
+use crate::error::Error;
+use crate::gradient::Gradient;
+use crate::named_colors;
+use crate::oklab::Oklab;
+use clap::ValueEnum;
+use rand::prelude::*;
+use serde::de::{self, EnumAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::env;
+use std::fmt::{self, Display};
 use termion::color;
 
 /// Colors used for displaying [FallingChar]
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub struct Colors {
     /// Trail [Color]
     pub trail: Color,
@@ -10,13 +19,201 @@ pub struct Colors {
     pub head: Color,
     /// [Color] of characters left behind
     pub left_behind: Color,
+    /// Optional [Gradient] of the trail, used instead of `trail` [Color]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gradient: Option<Gradient>,
+    /// Optional background [Color] of the whole screen, terminal default is used if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<Color>,
+}
+
+impl Colors {
+    /// [Colors] with `trail` color, head and left behind colors derived from it using `auto_colors`
+    pub fn from_trail(trail: Color, auto_colors: &AutoColors) -> Self {
+        Self {
+            head: trail.get_auto_head_color(auto_colors),
+            left_behind: trail.get_auto_left_behind_color(auto_colors),
+            trail,
+            gradient: None,
+            background: None,
+        }
+    }
+}
+
+/// How head and left behind colors are derived from trail color, see [Color::get_auto_head_color]
+///
+/// Values are changes of perceived lightness ([Oklab] lightness goes from 0.0 for black to 1.0 for white)
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(default)]
+pub struct AutoColors {
+    /// Lightness added to trail color to get head color
+    pub head_lightness: f32,
+    /// Lightness added to trail color to get left behind color, usually negative
+    pub left_behind_lightness: f32,
+}
+
+impl Default for AutoColors {
+    fn default() -> Self {
+        Self {
+            head_lightness: 0.2,
+            left_behind_lightness: -0.25,
+        }
+    }
+}
+
+/// How [Colors] of each new [FallingChar] are chosen
+///
+/// Config file uses the same text as `--color-mode`, see [ColorMode::parse]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum ColorMode {
+    /// Every faller uses configured [Colors]
+    #[default]
+    Fixed,
+    /// Every faller gets random trail color from the list, or random hue if the list is empty
+    Random(Vec<Color>),
+    /// Hue of new fallers changes over time
+    HueCycle {
+        /// Number of frames after which hue is back where it started
+        period: u32,
+    },
+    /// Hue changes with column, from red on the left to violet on the right
+    Rainbow,
+}
+
+/// Default [ColorMode::HueCycle] period, around 10 seconds
+const DEFAULT_HUE_CYCLE_PERIOD: u32 = 300;
+
+impl ColorMode {
+    /// Parse [ColorMode] from `mode`: `fixed`, `random[:color;color...]`, `hue-cycle[:period]` or `rainbow`
+    ///
+    /// Colors use syntax of [Color::parse]
+    pub fn parse(mode: &str) -> Result<Self, Error> {
+        let invalid = || {
+            Error::InvalidColor(format!(
+                "'{mode}' is not a color mode, use fixed, random[:color;color...], hue-cycle[:period] or rainbow"
+            ))
+        };
+        let (name, argument) = match mode.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (mode, None),
+        };
+        match (name.trim(), argument) {
+            ("fixed", None) => Ok(ColorMode::Fixed),
+            ("rainbow", None) => Ok(ColorMode::Rainbow),
+            ("random", None) => Ok(ColorMode::Random(
+                // whole 16-color palette but black and dark gray, which are hard to see
+                (1..=15).filter(|&color| color != 8).map(Color::Palette).collect(),
+            )),
+            ("random", Some(colors)) => Ok(ColorMode::Random(
+                colors
+                    .split(';')
+                    .filter(|color| !color.trim().is_empty())
+                    .map(Color::parse)
+                    .collect::<Result<_, _>>()?,
+            )),
+            ("hue-cycle", None) => Ok(ColorMode::HueCycle {
+                period: DEFAULT_HUE_CYCLE_PERIOD,
+            }),
+            ("hue-cycle", Some(period)) => match period.trim().parse::<u32>() {
+                Ok(period @ 1..) => Ok(ColorMode::HueCycle { period }),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Get [Colors] of new faller in `column` of screen `width` wide, `frame` frames after start
+    ///
+    /// Except [ColorMode::Fixed] which uses `colors`, head and left behind colors are derived from trail color
+    /// using `auto_colors`
+    pub fn colors_for(
+        &self,
+        colors: &Colors,
+        auto_colors: &AutoColors,
+        rng: &mut dyn RngCore,
+        column: u16,
+        width: u16,
+        frame: u64,
+    ) -> Colors {
+        let trail = match self {
+            ColorMode::Fixed => return colors.clone(),
+            ColorMode::Random(palette) => match palette.choose(rng) {
+                Some(color) => color.clone(),
+                None => Color::from_hue(rng.gen_range(0.0..360.0)),
+            },
+            ColorMode::HueCycle { period } => {
+                let period = u64::from(*period).max(1);
+                Color::from_hue((frame % period) as f32 / period as f32 * 360.0)
+            }
+            ColorMode::Rainbow => {
+                // stop at violet, so that both edges of the screen do not end up red
+                Color::from_hue(f32::from(column.saturating_sub(1)) / f32::from(width.max(1)) * 300.0)
+            }
+        };
+        Colors {
+            background: colors.background.clone(),
+            ..Colors::from_trail(trail, auto_colors)
+        }
+    }
+}
+
+/// Number of colors which terminal can display
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum ColorDepth {
+    /// Only 16-color palette, see [Color::Palette]
+    #[value(name = "16")]
+    Ansi16,
+    /// 256-color palette, see [Color::Ansi256]
+    #[value(name = "256")]
+    Ansi256,
+    /// Any RGB color
+    #[value(name = "truecolor")]
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Detect [ColorDepth] of the terminal from `COLORTERM` and `TERM` environment variables
+    pub fn detect() -> Self {
+        Self::from_env(
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    /// Get [ColorDepth] from values of `COLORTERM` and `TERM` environment variables
+    fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return ColorDepth::TrueColor;
+        }
+        match term {
+            Some(term) if term.contains("truecolor") || term.contains("direct") => {
+                ColorDepth::TrueColor
+            }
+            Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// Levels of each component in 6x6x6 color cube of 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Squared distance between two RGB colors
+fn distance(first: (u8, u8, u8), second: (u8, u8, u8)) -> u32 {
+    let component = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2) as u32;
+    component(first.0, second.0) + component(first.1, second.1) + component(first.2, second.2)
 }
 
 /// Enum for Color
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+///
+/// In config file it is written in the same syntax as accepted by [Color::parse],
+/// older `!Palette 2` and `!RGB {r: 1, g: 2, b: 3}` forms are still accepted
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Color {
     /// Color from 16-color palette of terminal
     Palette(u8),
+    /// Color from 256-color palette of terminal
+    Ansi256(u8),
     /// RGB color
     RGB {
         /// Red
@@ -28,31 +225,136 @@ pub enum Color {
     },
 }
 
-macro_rules! add_offset_to_u8 {
-    ($num: ident, $offset: ident) => {
-        let mut $num = *$num as i16;
-        $num += $offset;
-        if $num > 255 {
-            $num = 255;
-        } else if $num < 0 {
-            $num = 0;
+impl Color {
+    /// Create [Color::RGB] from vector `rgb` values, vector needs to have exactly three values
+    pub fn rgb_from_vec(rgb: Vec<u8>) -> Result<Color, Error> {
+        match rgb[..] {
+            [r, g, b] => Ok(Color::RGB { r, g, b }),
+            _ => Err(Error::InvalidColor(format!(
+                "RGB color needs exactly 3 values, got {}",
+                rgb.len()
+            ))),
         }
-        let $num = $num as u8;
-    };
-}
+    }
 
-impl Color {
-    /// Create [Color::RGB] from vector `rgb` values, only first three values will be used (indices 0 to 2)
-    ///
-    /// This does NOT check if vector has at least 3 values, so might result in panic while accessing elements
-    pub fn rgb_from_vec(rgb: Vec<u8>) -> Color {
+    /// Parse [Color] from `color`, which can be:
+    /// - palette number: `2` (0-15 is [Color::Palette], 16-255 is [Color::Ansi256])
+    /// - hex: `#7ede5a` or `#7e5`
+    /// - `rgb(127,222,90)` or just `127,222,90`
+    /// - `hsl(100,60%,60%)`
+    /// - CSS/X11 color name: `darkolivegreen`
+    pub fn parse(color: &str) -> Result<Color, Error> {
+        let color = color.trim();
+        if let Ok(index) = color.parse::<u8>() {
+            return Ok(match index {
+                0..=15 => Color::Palette(index),
+                _ => Color::Ansi256(index),
+            });
+        }
+        let lowercase = color.to_ascii_lowercase();
+        let invalid = || {
+            Error::InvalidColor(format!(
+                "'{color}' is not a color, use palette number (0-255), #rrggbb, rgb(r,g,b), hsl(h,s%,l%) or color name"
+            ))
+        };
+        if let Some(hex) = lowercase.strip_prefix('#') {
+            return Color::parse_hex(hex).ok_or_else(invalid);
+        }
+        if let Some(rgb) = function_arguments(&lowercase, "rgb") {
+            return Color::parse_rgb(rgb);
+        }
+        if let Some(hsl) = function_arguments(&lowercase, "hsl") {
+            return Color::parse_hsl(hsl).ok_or_else(invalid);
+        }
+        if lowercase.contains(',') {
+            return Color::parse_rgb(color);
+        }
+        named_colors::lookup(&lowercase)
+            .map(|(r, g, b)| Color::RGB { r, g, b })
+            .ok_or_else(invalid)
+    }
+
+    /// Parse `#` prefixed hex color without the prefix, `rrggbb` or `rgb`
+    fn parse_hex(hex: &str) -> Option<Color> {
+        if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return None;
+        }
+        let component = |digits: &str| u8::from_str_radix(digits, 16).ok();
+        match hex.len() {
+            6 => Some(Color::RGB {
+                r: component(&hex[0..2])?,
+                g: component(&hex[2..4])?,
+                b: component(&hex[4..6])?,
+            }),
+            // each digit is repeated, e.g. #7e5 is #77ee55
+            3 => Some(Color::RGB {
+                r: component(&hex[0..1])? * 17,
+                g: component(&hex[1..2])? * 17,
+                b: component(&hex[2..3])? * 17,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Parse `h,s%,l%` arguments of `hsl()`, hue in degrees, saturation and lightness in percent
+    fn parse_hsl(hsl: &str) -> Option<Color> {
+        let values: Vec<f32> = hsl
+            .split(',')
+            .map(|value| value.trim().trim_end_matches('%').parse::<f32>().ok())
+            .collect::<Option<_>>()?;
+        let [hue, saturation, lightness] = values[..] else {
+            return None;
+        };
+        if !(0.0..=100.0).contains(&saturation) || !(0.0..=100.0).contains(&lightness) {
+            return None;
+        }
+        let (saturation, lightness) = (saturation / 100.0, lightness / 100.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Some(Color::from_hue_chroma(hue, chroma, lightness - chroma / 2.0))
+    }
+
+    /// [Color::RGB] with `hue` in degrees, `chroma` and `offset` added to each component (0.0 - 1.0)
+    fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Color {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let component = |value: f32| ((value + offset) * 255.0).round() as u8;
         Color::RGB {
-            r: rgb[0],
-            g: rgb[1],
-            b: rgb[2],
+            r: component(r),
+            g: component(g),
+            b: component(b),
         }
     }
 
+    /// Bright [Color::RGB] with `hue` in degrees (0-360)
+    pub fn from_hue(hue: f32) -> Color {
+        const SATURATION: f32 = 0.8;
+        const VALUE: f32 = 0.95;
+        let chroma = VALUE * SATURATION;
+        Color::from_hue_chroma(hue, chroma, VALUE - chroma)
+    }
+
+    /// Parse [Color::RGB] from `rgb` in r,g,b syntax (0-255 each) e.g.: 128,128,255
+    pub fn parse_rgb(rgb: &str) -> Result<Color, Error> {
+        let colors_int = rgb
+            .split(',')
+            .map(|s| s.trim().parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                Error::InvalidColor(format!(
+                    "'{rgb}' is not a RGB color, use r,g,b syntax (0-255 each) e.g.: 128,128,255"
+                ))
+            })?;
+        Color::rgb_from_vec(colors_int)
+    }
+
     /// Get ANSI string for [Color]
     pub fn get_ansi_string(&self) -> String {
         match self {
@@ -75,47 +377,230 @@ impl Color {
                 _ => color::Black.fg_str(),
             }
             .to_owned(),
+            Color::Ansi256(color) => color::AnsiValue(*color).fg_string(),
             Color::RGB { r, g, b } => color::Rgb(*r, *g, *b).fg_string(),
         }
     }
 
-    /// Get default head color based on `self` color
-    pub fn get_auto_head_color(&self) -> Color {
+    /// Get ANSI string setting [Color] as background
+    pub fn get_ansi_bg_string(&self) -> String {
         match self {
-            Color::Palette(color) => {
-                match color {
-                    0..=7 => Color::Palette(color + 8),
-                    8..=15 => Color::Palette(color - 8),
-                    _ => self.clone(), // this should never happen, but just in case...
-                }
+            // termion's named palette colors use the same escape codes as their palette numbers
+            Color::Palette(color @ 0..=15) | Color::Ansi256(color) => color::AnsiValue(*color).bg_string(),
+            Color::Palette(_) => color::Black.bg_str().to_owned(),
+            Color::RGB { r, g, b } => color::Rgb(*r, *g, *b).bg_string(),
+        }
+    }
+
+    /// Get red, green and blue components of [Color], palette colors use default xterm values
+    pub fn to_rgb(&self) -> (u8, u8, u8) {
+        match self {
+            Color::Palette(color) => match color {
+                1 => (205, 0, 0),
+                2 => (0, 205, 0),
+                3 => (205, 205, 0),
+                4 => (0, 0, 238),
+                5 => (205, 0, 205),
+                6 => (0, 205, 205),
+                7 => (229, 229, 229),
+                8 => (127, 127, 127),
+                9 => (255, 0, 0),
+                10 => (0, 255, 0),
+                11 => (255, 255, 0),
+                12 => (92, 92, 255),
+                13 => (255, 0, 255),
+                14 => (0, 255, 255),
+                15 => (255, 255, 255),
+                _ => (0, 0, 0),
+            },
+            Color::Ansi256(color @ 0..=15) => Color::Palette(*color).to_rgb(),
+            Color::Ansi256(color @ 16..=231) => {
+                let index = color - 16;
+                (
+                    CUBE_LEVELS[usize::from(index / 36)],
+                    CUBE_LEVELS[usize::from(index / 6 % 6)],
+                    CUBE_LEVELS[usize::from(index % 6)],
+                )
             }
-            Color::RGB { r, g, b } => {
-                let color_offset = 15i16;
+            Color::Ansi256(color) => {
+                let level = 8 + (color - 232) * 10;
+                (level, level, level)
+            }
+            Color::RGB { r, g, b } => (*r, *g, *b),
+        }
+    }
 
-                add_offset_to_u8!(r, color_offset);
-                add_offset_to_u8!(g, color_offset);
-                add_offset_to_u8!(b, color_offset);
-                Color::RGB { r, g, b }
+    /// Get [Color] which can be displayed by terminal with `depth`, the closest one if `self` cannot
+    pub fn downgrade(&self, depth: ColorDepth) -> Color {
+        match (self, depth) {
+            (Color::Palette(_), _) | (_, ColorDepth::TrueColor) => self.clone(),
+            (Color::Ansi256(_), ColorDepth::Ansi256) => self.clone(),
+            (Color::RGB { .. }, ColorDepth::Ansi256) => Color::nearest_ansi256(self.to_rgb()),
+            (Color::Ansi256(_) | Color::RGB { .. }, ColorDepth::Ansi16) => {
+                let rgb = self.to_rgb();
+                let nearest = (0..16)
+                    .min_by_key(|&color| distance(Color::Palette(color).to_rgb(), rgb))
+                    .unwrap_or_default();
+                Color::Palette(nearest)
             }
         }
     }
 
-    /// Get default left behind color based on `self` color
-    pub fn get_auto_left_behind_color(&self) -> Color {
+    /// Get [Color::Ansi256] from color cube or grayscale ramp which is the closest to `rgb`
+    fn nearest_ansi256(rgb: (u8, u8, u8)) -> Color {
+        let nearest_level = |component: u8| {
+            (0..CUBE_LEVELS.len())
+                .min_by_key(|&level| CUBE_LEVELS[level].abs_diff(component))
+                .unwrap_or_default() as u8
+        };
+        let cube = Color::Ansi256(
+            16 + 36 * nearest_level(rgb.0) + 6 * nearest_level(rgb.1) + nearest_level(rgb.2),
+        );
+        let gray = (232..=255)
+            .map(Color::Ansi256)
+            .min_by_key(|color| distance(color.to_rgb(), rgb))
+            .unwrap_or(Color::Ansi256(232));
+        if distance(cube.to_rgb(), rgb) <= distance(gray.to_rgb(), rgb) {
+            cube
+        } else {
+            gray
+        }
+    }
+
+    /// Same [Color] with perceived lightness changed by `delta`, see [Oklab]
+    ///
+    /// Palette colors use their default RGB values, result is always [Color::RGB]
+    pub fn with_lightness(&self, delta: f32) -> Color {
+        let mut oklab = Oklab::from_rgb(self.to_rgb());
+        oklab.lightness = (oklab.lightness + delta).clamp(0.0, 1.0);
+        let (r, g, b) = oklab.to_rgb();
+        Color::RGB { r, g, b }
+    }
+
+    /// Get default head color based on `self` color, lighter by [AutoColors::head_lightness]
+    pub fn get_auto_head_color(&self, auto_colors: &AutoColors) -> Color {
+        self.with_lightness(auto_colors.head_lightness)
+    }
+
+    /// Get default left behind color based on `self` color, darker by [AutoColors::left_behind_lightness]
+    pub fn get_auto_left_behind_color(&self, auto_colors: &AutoColors) -> Color {
+        self.with_lightness(auto_colors.left_behind_lightness)
+    }
+}
+
+/// Get arguments of `function(arguments)` call in `text`
+fn function_arguments<'a>(text: &'a str, function: &str) -> Option<&'a str> {
+    text.strip_prefix(function)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+impl Display for Color {
+    /// Palette colors are written as their number, RGB colors as `#rrggbb`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Color::Palette(color) => Color::Palette(*color),
-            Color::RGB { r, g, b } => {
-                let color_offset = -30i16;
+            Color::Palette(color) | Color::Ansi256(color) => write!(f, "{color}"),
+            Color::RGB { r, g, b } => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+        }
+    }
+}
 
-                add_offset_to_u8!(r, color_offset);
-                add_offset_to_u8!(g, color_offset);
-                add_offset_to_u8!(b, color_offset);
-                Color::RGB { r, g, b }
+impl Display for ColorMode {
+    /// Written in the form accepted by [ColorMode::parse]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorMode::Fixed => write!(f, "fixed"),
+            ColorMode::Random(palette) => {
+                let colors: Vec<String> = palette.iter().map(Color::to_string).collect();
+                write!(f, "random:{}", colors.join(";"))
             }
+            ColorMode::HueCycle { period } => write!(f, "hue-cycle:{period}"),
+            ColorMode::Rainbow => write!(f, "rainbow"),
+        }
+    }
+}
+
+impl Serialize for ColorMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ColorMode, D::Error> {
+        let mode = String::deserialize(deserializer)?;
+        ColorMode::parse(&mode).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Color::Palette(color) | Color::Ansi256(color) => serializer.serialize_u8(*color),
+            Color::RGB { .. } => serializer.collect_str(self),
         }
     }
 }
 
+/// Components of `!RGB` tagged color in older config files
+#[derive(Deserialize)]
+struct RgbComponents {
+    /// Red
+    r: u8,
+    /// Green
+    g: u8,
+    /// Blue
+    b: u8,
+}
+
+/// [Visitor] deserializing [Color] from text, palette number or older tagged forms
+struct ColorVisitor;
+
+impl<'de> Visitor<'de> for ColorVisitor {
+    type Value = Color;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a color, e.g. 2, '#7ede5a', rgb(127,222,90), hsl(100,60%,60%) or name")
+    }
+
+    fn visit_u64<E: de::Error>(self, color: u64) -> Result<Color, E> {
+        let color = u8::try_from(color)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(color), &self))?;
+        Color::parse(&color.to_string()).map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, color: i64) -> Result<Color, E> {
+        Err(E::invalid_value(de::Unexpected::Signed(color), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, color: &str) -> Result<Color, E> {
+        Color::parse(color).map_err(E::custom)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Color, A::Error> {
+        let (variant, access): (String, _) = data.variant()?;
+        match variant.as_str() {
+            "Palette" => Ok(Color::Palette(access.newtype_variant()?)),
+            "Ansi256" => Ok(Color::Ansi256(access.newtype_variant()?)),
+            "RGB" => {
+                let RgbComponents { r, g, b } = access.newtype_variant()?;
+                Ok(Color::RGB { r, g, b })
+            }
+            _ => Err(de::Error::unknown_variant(
+                &variant,
+                &["Palette", "Ansi256", "RGB"],
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -129,7 +614,9 @@ mod test {
 
     #[test]
     fn rgb_from_vec() {
-        let c = Color::RGB { r: 2, g: 4, b: 8 };
+        let c = Color::rgb_from_vec(vec![2, 4, 8]).unwrap();
+        assert!(Color::rgb_from_vec(vec![2, 4]).is_err());
+        assert!(Color::rgb_from_vec(vec![2, 4, 8, 16]).is_err());
         match c {
             Color::RGB { r, g, b } => {
                 assert_eq!(r, 2);
@@ -140,34 +627,205 @@ mod test {
         }
     }
 
+    /// Perceived lightness of `color`
+    fn lightness(color: &Color) -> f32 {
+        Oklab::from_rgb(color.to_rgb()).lightness
+    }
+
     #[test]
     fn rgb_auto_head_color() {
+        let auto_colors = AutoColors::default();
         let c = Color::RGB { r: 255, g: 250, b: 10 };
-        let head_color = c.get_auto_head_color();
+        let head_color = c.get_auto_head_color(&auto_colors);
+        assert!(lightness(&head_color) > lightness(&c) + 0.02);
+        // saturated blue stays blue instead of turning white
+        let c = Color::RGB { r: 0, g: 0, b: 255 };
+        let head_color = c.get_auto_head_color(&auto_colors);
+        assert!((lightness(&head_color) - lightness(&c) - 0.2).abs() < 0.01);
         match head_color {
-            Color::RGB { r, g, b } => {
-                assert_eq!(r, 255);
-                assert_eq!(g, 255);
-                assert_eq!(b, 25);
-            },
+            Color::RGB { r, g, b } => assert!(b > r && b > g),
             _ => { panic!("Got non RGB color"); }
         }
     }
 
     #[test]
     fn rgb_left_behind_color() {
+        let auto_colors = AutoColors::default();
         let c = Color::RGB { r: 255, g: 250, b: 10 };
-        let left_behind_color = c.get_auto_left_behind_color();
+        let left_behind_color = c.get_auto_left_behind_color(&auto_colors);
+        assert!((lightness(&c) - lightness(&left_behind_color) - 0.25).abs() < 0.01);
         match left_behind_color {
-            Color::RGB { r, g, b } => {
-                assert_eq!(r, 225);
-                assert_eq!(g, 220);
-                assert_eq!(b, 0);
-            },
+            Color::RGB { r, g, b } => assert!(r > b && g > b),
             _ => { panic!("Got non RGB color"); }
         }
     }
 
+    #[test]
+    fn configurable_lightness() {
+        let c = Color::RGB { r: 127, g: 222, b: 90 };
+        let auto_colors = AutoColors {
+            head_lightness: 0.0,
+            left_behind_lightness: -1.0,
+        };
+        assert_eq!(c.get_auto_head_color(&auto_colors), c);
+        assert_eq!(c.get_auto_left_behind_color(&auto_colors), Color::RGB { r: 0, g: 0, b: 0 });
+    }
+
+    #[test]
+    fn to_rgb() {
+        assert_eq!(Color::RGB { r: 1, g: 2, b: 3 }.to_rgb(), (1, 2, 3));
+        assert_eq!(Color::Palette(10).to_rgb(), (0, 255, 0));
+        assert_eq!(Color::Palette(200).to_rgb(), (0, 0, 0));
+    }
+
+    #[test]
+    fn ansi256() {
+        assert_eq!(Color::Ansi256(196).get_ansi_string(), "\u{1b}[38;5;196m");
+        assert_eq!(Color::Ansi256(196).to_rgb(), (255, 0, 0));
+        assert_eq!(Color::Ansi256(244).to_rgb(), (128, 128, 128));
+    }
+
+    #[test]
+    fn ansi_bg_string() {
+        assert_eq!(Color::Palette(4).get_ansi_bg_string(), "\u{1b}[48;5;4m");
+        assert_eq!(Color::Ansi256(196).get_ansi_bg_string(), "\u{1b}[48;5;196m");
+        assert_eq!(Color::RGB { r: 1, g: 2, b: 3 }.get_ansi_bg_string(), "\u{1b}[48;2;1;2;3m");
+    }
+
+    #[test]
+    fn downgrade() {
+        let c = Color::RGB { r: 250, g: 5, b: 10 };
+        assert_eq!(c.downgrade(ColorDepth::TrueColor), c);
+        assert_eq!(c.downgrade(ColorDepth::Ansi256), Color::Ansi256(196));
+        assert_eq!(c.downgrade(ColorDepth::Ansi16), Color::Palette(9));
+        let gray = Color::RGB { r: 130, g: 128, b: 127 };
+        assert_eq!(gray.downgrade(ColorDepth::Ansi256), Color::Ansi256(244));
+        assert_eq!(Color::Ansi256(46).downgrade(ColorDepth::Ansi16), Color::Palette(10));
+        assert_eq!(Color::Palette(3).downgrade(ColorDepth::Ansi16), Color::Palette(3));
+    }
+
+    #[test]
+    fn parse() {
+        let rgb = |r, g, b| Color::RGB { r, g, b };
+        assert_eq!(Color::parse("2").unwrap(), Color::Palette(2));
+        assert_eq!(Color::parse(" 120 ").unwrap(), Color::Ansi256(120));
+        assert_eq!(Color::parse("#7ede5a").unwrap(), rgb(126, 222, 90));
+        assert_eq!(Color::parse("#7E5").unwrap(), rgb(119, 238, 85));
+        assert_eq!(Color::parse("rgb(127, 222, 90)").unwrap(), rgb(127, 222, 90));
+        assert_eq!(Color::parse("127,222,90").unwrap(), rgb(127, 222, 90));
+        assert_eq!(Color::parse("hsl(120,100%,25%)").unwrap(), rgb(0, 128, 0));
+        assert_eq!(Color::parse("HSL(0, 0%, 100%)").unwrap(), rgb(255, 255, 255));
+        assert_eq!(Color::parse("DarkOliveGreen").unwrap(), rgb(85, 107, 47));
+        for invalid in ["256", "#12345", "#ggg", "rgb(1,2)", "hsl(1,200%,5%)", "matrix", ""] {
+            assert!(Color::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn serialization() {
+        let colors = vec![Color::Palette(2), Color::Ansi256(200), Color::RGB { r: 126, g: 222, b: 90 }];
+        let yaml = serde_yaml::to_string(&colors).unwrap();
+        assert_eq!(yaml, "- 2\n- 200\n- '#7ede5a'\n");
+        assert_eq!(serde_yaml::from_str::<Vec<Color>>(&yaml).unwrap(), colors);
+    }
+
+    #[test]
+    fn deserialize_older_forms() {
+        let yaml = "[!Palette 3, !Ansi256 100, !RGB {r: 1, g: 2, b: 3}, crimson, 'hsl(0,100%,50%)']";
+        assert_eq!(
+            serde_yaml::from_str::<Vec<Color>>(yaml).unwrap(),
+            [
+                Color::Palette(3),
+                Color::Ansi256(100),
+                Color::RGB { r: 1, g: 2, b: 3 },
+                Color::RGB { r: 220, g: 20, b: 60 },
+                Color::RGB { r: 255, g: 0, b: 0 },
+            ]
+        );
+        assert!(serde_yaml::from_str::<Color>("notacolor").is_err());
+        assert!(serde_yaml::from_str::<Color>("300").is_err());
+    }
+
+    #[test]
+    fn from_hue() {
+        assert_eq!(Color::from_hue(0.0), Color::RGB { r: 242, g: 48, b: 48 });
+        assert_eq!(Color::from_hue(120.0), Color::RGB { r: 48, g: 242, b: 48 });
+        assert_eq!(Color::from_hue(600.0), Color::from_hue(240.0));
+    }
+
+    #[test]
+    fn color_mode_parse() {
+        assert_eq!(ColorMode::parse("fixed").unwrap(), ColorMode::Fixed);
+        assert_eq!(ColorMode::parse("rainbow").unwrap(), ColorMode::Rainbow);
+        assert_eq!(ColorMode::parse("hue-cycle:60").unwrap(), ColorMode::HueCycle { period: 60 });
+        assert_eq!(
+            ColorMode::parse("random:3;200;1,2,3").unwrap(),
+            ColorMode::Random(vec![Color::Palette(3), Color::Ansi256(200), Color::RGB { r: 1, g: 2, b: 3 }])
+        );
+        match ColorMode::parse("random").unwrap() {
+            ColorMode::Random(palette) => assert_eq!(palette.len(), 14),
+            _ => panic!("Got non random color mode"),
+        }
+        assert!(ColorMode::parse("hue-cycle:0").is_err());
+        assert!(ColorMode::parse("fixed:1").is_err());
+        assert!(ColorMode::parse("sparkles").is_err());
+    }
+
+    #[test]
+    fn color_mode_serialization() {
+        let modes = [
+            ColorMode::Fixed,
+            ColorMode::Rainbow,
+            ColorMode::HueCycle { period: 30 },
+            ColorMode::Random(vec![Color::Palette(3), Color::RGB { r: 1, g: 2, b: 3 }]),
+            ColorMode::Random(Vec::new()),
+        ];
+        let yaml = serde_yaml::to_string(&modes).unwrap();
+        assert_eq!(
+            yaml,
+            "- fixed\n- rainbow\n- hue-cycle:30\n- random:3;#010203\n- 'random:'\n"
+        );
+        assert_eq!(serde_yaml::from_str::<Vec<ColorMode>>(&yaml).unwrap(), modes);
+        assert_eq!(
+            serde_yaml::from_str::<ColorMode>("hue-cycle").unwrap(),
+            ColorMode::HueCycle { period: DEFAULT_HUE_CYCLE_PERIOD }
+        );
+        assert!(serde_yaml::from_str::<ColorMode>("!HueCycle 30").is_err());
+    }
+
+    #[test]
+    fn color_modes() {
+        let auto_colors = AutoColors::default();
+        let colors = Colors::from_trail(Color::Palette(2), &auto_colors);
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0);
+        let mut colors_for = |mode: &ColorMode, column, frame| {
+            mode.colors_for(&colors, &auto_colors, &mut rng, column, 60, frame).trail
+        };
+        assert_eq!(colors_for(&ColorMode::Fixed, 30, 0), Color::Palette(2));
+        assert_eq!(
+            colors_for(&ColorMode::Random(vec![Color::Palette(5)]), 30, 0),
+            Color::Palette(5)
+        );
+        let hue_cycle = ColorMode::HueCycle { period: 30 };
+        assert_eq!(colors_for(&hue_cycle, 1, 10), Color::from_hue(120.0));
+        assert_eq!(colors_for(&hue_cycle, 1, 40), Color::from_hue(120.0));
+        assert_eq!(colors_for(&ColorMode::Rainbow, 1, 0), Color::from_hue(0.0));
+        assert_eq!(colors_for(&ColorMode::Rainbow, 31, 0), Color::from_hue(150.0));
+        let derived = ColorMode::Rainbow.colors_for(&colors, &auto_colors, &mut rng, 1, 60, 0);
+        assert_eq!(derived.head, derived.trail.get_auto_head_color(&auto_colors));
+        assert_eq!(derived.left_behind, derived.trail.get_auto_left_behind_color(&auto_colors));
+    }
+
+    #[test]
+    fn color_depth_from_env() {
+        let depth = ColorDepth::from_env;
+        assert_eq!(depth(Some("truecolor"), Some("xterm")), ColorDepth::TrueColor);
+        assert_eq!(depth(None, Some("xterm-direct")), ColorDepth::TrueColor);
+        assert_eq!(depth(None, Some("screen-256color")), ColorDepth::Ansi256);
+        assert_eq!(depth(Some(""), Some("linux")), ColorDepth::Ansi16);
+        assert_eq!(depth(None, None), ColorDepth::Ansi16);
+    }
+
     #[test]
     fn pallete_ansi_string() {
         let c = Color::Palette(3);
@@ -178,24 +836,22 @@ mod test {
     #[test]
     fn pallete_head_color() {
         let c = Color::Palette(4);
-        let head_color = c.get_auto_head_color();
+        let head_color = c.get_auto_head_color(&AutoColors::default());
+        assert!(lightness(&head_color) > lightness(&c) + 0.15);
         match head_color {
-            Color::Palette(p) => {
-                assert_eq!(p, 12);
-            },
-            _ => { panic!("Got non Palette color"); }
+            Color::RGB { r, g, b } => assert!(b > r && b > g),
+            _ => { panic!("Got non RGB color"); }
         }
     }
 
     #[test]
     fn pallete_auto_left_behind_color() {
         let c = Color::Palette(4);
-        let left_behind_color = c.get_auto_head_color();
+        let left_behind_color = c.get_auto_left_behind_color(&AutoColors::default());
+        assert!(lightness(&left_behind_color) < lightness(&c) - 0.2);
         match left_behind_color {
-            Color::Palette(p) => {
-                assert_eq!(p, 12);
-            },
-            _ => { panic!("Got non Palette color"); }
+            Color::RGB { r, g, b } => assert!(b > r && b > g),
+            _ => { panic!("Got non RGB color"); }
         }
     }
 }
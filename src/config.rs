@@ -4,13 +4,26 @@
 
 use crate::position::{CenteredPosition, PositionTrait, PositionType};
 use crate::{
-    colors::{Color, Colors},
+    charset,
+    colors::{AutoColors, Color, ColorDepth, ColorMode, Colors},
+    controls::{Action, KeyBindings},
+    error::Error,
+    falling_char::Speed,
+    gradient::{Easing, Gradient},
+    headless::DumpFormat,
     message::{Message, TextType},
+    residue::Decay,
+    theme::Theme,
     Position,
 };
 use clap::Parser;
 use derive_getters::Getters;
+use serde::de::IgnoredAny;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
 use termion::terminal_size;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -43,31 +56,167 @@ pub struct Config {
     /// Configured [ColorPair] which will be used by the fallers
     #[serde(skip_serializing_if = "skip_if_default_colors")]
     colors: Colors,
+    /// [AutoColors] deriving head and left behind colors from trail color
+    #[serde(skip_serializing_if = "skip_if_default_auto_colors")]
+    auto_colors: AutoColors,
+    /// [ColorMode] choosing colors of each faller
+    #[serde(skip_serializing_if = "skip_if_default_color_mode")]
+    color_mode: ColorMode,
     /// Maximum number of fallers
     #[serde(skip_serializing_if = "skip_if_default_no_fallers")]
     no_fallers: usize,
+    /// [Speed] range of fallers
+    #[serde(skip_serializing_if = "skip_if_default_speed")]
+    speed: Speed,
+    /// Number of simulation steps and rendered frames per second
+    #[serde(skip_serializing_if = "skip_if_default_fps")]
+    fps: u32,
+    /// How fast simulated time passes compared to real time, e.g. 0.5 for slow motion
+    #[serde(skip_serializing_if = "skip_if_default_time_scale")]
+    time_scale: f32,
     /// [String] which characters will be used for displaying [FallingChar] and its trail
     #[serde(skip_serializing_if = "skip_if_default_chars_to_use")]
     chars_to_use: String,
+    /// Charset specification overriding `chars_to_use`, see [charset::parse]
+    #[serde(skip_serializing_if = "skip_if_default_charset")]
+    charset: Option<String>,
+    /// [Decay] of characters left behind by fallers
+    #[serde(skip_serializing_if = "skip_if_default_decay")]
+    decay: Decay,
     /// Optional message which will be displayed on the screen
     #[serde(skip_serializing_if = "skip_if_default_message")]
     message: Option<Message>,
+    /// Seed for random number generator, same seed and screen size give the same output
+    #[serde(skip_serializing_if = "skip_if_default_seed")]
+    seed: Option<u64>,
+    /// Name of [Theme] which overrides colors, message color and chars to use not set in the config file
+    #[serde(skip_serializing_if = "skip_if_default_theme")]
+    theme: Option<String>,
+    /// Key names mapped to [Action]s overriding default [KeyBindings], null unbinds the key
+    #[serde(skip_serializing_if = "skip_if_default_keys")]
+    keys: BTreeMap<String, Option<Action>>,
+    /// Settings which were set in the config file, [Theme] does not override them
+    #[serde(skip)]
+    #[getter(skip)]
+    file_settings: FileSettings,
+}
+
+/// Settings overridden by [Theme] which are set in the config file, presence of each is all that matters
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+struct FileSettings {
+    /// `colors` are set
+    colors: Option<IgnoredAny>,
+    /// `chars_to_use` are set
+    chars_to_use: Option<IgnoredAny>,
+    /// `message` is set, including its color
+    message: Option<IgnoredAny>,
 }
 
 gen_skip_if_default!(screen_size, Position);
 gen_skip_if_default!(colors, Colors);
+gen_skip_if_default!(auto_colors, AutoColors);
+gen_skip_if_default!(color_mode, ColorMode);
 gen_skip_if_default!(no_fallers, usize);
+gen_skip_if_default!(speed, Speed);
+gen_skip_if_default!(fps, u32);
+gen_skip_if_default!(time_scale, f32);
 gen_skip_if_default!(chars_to_use, String);
+gen_skip_if_default!(charset, Option<String>);
+gen_skip_if_default!(decay, Decay);
 gen_skip_if_default!(message, Option<Message>);
+gen_skip_if_default!(seed, Option<u64>);
+gen_skip_if_default!(theme, Option<String>);
+gen_skip_if_default!(keys, BTreeMap<String, Option<Action>>);
 
 impl Config {
+    /// Load [Config] from YAML `config_file` (defaults without it) and overwrite it with parameters from `cli`
+    pub fn load(config_file: Option<&str>, cli: &Cli) -> Result<Self, Error> {
+        let mut config: Config = match config_file {
+            Some(config_file) => {
+                let config_string = fs::read_to_string(config_file).map_err(|e| {
+                    Error::ConfigParse(format!("cannot read config file '{config_file}': {e}"))
+                })?;
+                let mut config: Config = serde_yaml::from_str(&config_string).map_err(|e| {
+                    Error::ConfigParse(format!("incorrect config file '{config_file}': {e}"))
+                })?;
+                config.file_settings = serde_yaml::from_str(&config_string).unwrap_or_default();
+                config
+            }
+            None => Config::default(),
+        };
+        config.apply_cli(cli)?;
+        Ok(config)
+    }
+
+    /// Set new screen size, e.g. after terminal was resized, message is centered again
+    pub fn set_screen_size(&mut self, size: Position) {
+        self.screen_size = size;
+        if let Some(ref mut message) = self.message {
+            message.bounds = size;
+            // message which does not fit stays where it was, it is just partially hidden
+            let _ = message.update_position();
+        }
+    }
+
+    /// Set maximum number of fallers, at least one
+    pub fn set_no_fallers(&mut self, no_fallers: usize) {
+        self.no_fallers = no_fallers.max(1);
+    }
+
+    /// Use `trail` color with head and left behind colors derived from it for all fallers,
+    /// gradient and background are kept
+    pub fn set_trail_color(&mut self, trail: Color) {
+        self.colors = Colors {
+            gradient: self.colors.gradient.clone(),
+            background: self.colors.background.clone(),
+            ..Colors::from_trail(trail, &self.auto_colors)
+        };
+        self.color_mode = ColorMode::Fixed;
+    }
+
+    /// Set `message` displayed on the screen, it is centered on current screen
+    pub fn set_message(&mut self, message: Option<Message>) {
+        self.message = message;
+        self.set_screen_size(self.screen_size);
+    }
+
+    /// Simulated time of one simulation step, see [Config::fps]
+    pub fn step_duration(&self) -> Duration {
+        Duration::from_secs(1) / self.fps.max(1)
+    }
+
+    /// Real time for which one simulated frame is shown, longer in slow motion, see [Config::time_scale]
+    pub fn frame_duration(&self) -> Duration {
+        self.step_duration().div_f32(self.time_scale)
+    }
+
+    /// Use colors, message color and chars to use from `theme`
+    pub fn apply_theme(&mut self, theme: &Theme) {
+        self.colors = theme.colors.clone();
+        if let Some(ref color) = theme.message_color {
+            if let Some(ref mut message) = self.message {
+                message.color = color.clone();
+            }
+        }
+        if let Some(ref chars_to_use) = theme.chars_to_use {
+            self.chars_to_use = chars_to_use.clone();
+        }
+    }
+
     /// Parse [Config] from [Cli] (via clap).
     ///
     /// Overwrite defaults with parameters from Cli, or do not if parameter is not present.
     ///
+    /// Returns [Error] if any of the parameters is incorrect, or message does not fit on the screen.
+    pub fn parse_cli(&mut self) -> Result<(), Error> {
+        self.apply_cli(&Cli::parse())
+    }
+
+    /// Overwrite config with parameters from already parsed `cli`, see [Config::parse_cli]
+    ///
     /// TODO: Evaluate and remove unnecessary clones in this method
-    pub fn parse_cli(&mut self) {
-        let cli = Cli::parse();
+    pub fn apply_cli(&mut self, cli: &Cli) -> Result<(), Error> {
 
         let size = match (cli.size_x, cli.size_y) {
             (Some(x), Some(y)) => Position::new(x, y),
@@ -77,37 +226,79 @@ impl Config {
         };
         self.screen_size = size;
 
+        // theme goes first, so that colors and chars from command line can still override it
+        if cli.theme.is_some() {
+            self.theme = cli.theme.clone();
+        }
+        if let Some(ref name) = self.theme {
+            let themes_dir = cli.themes_dir.clone().or_else(Theme::default_dir);
+            let mut theme = Theme::load(name, themes_dir.as_deref())?;
+            // settings from config file win over theme selected in the same file or on command line
+            if self.file_settings.colors.is_some() {
+                theme.colors = self.colors.clone();
+            }
+            if self.file_settings.chars_to_use.is_some() {
+                theme.chars_to_use = None;
+            }
+            if self.file_settings.message.is_some() {
+                theme.message_color = None;
+            }
+            self.apply_theme(&theme);
+        }
+
+        for (lightness, field) in [
+            (cli.head_lightness, &mut self.auto_colors.head_lightness),
+            (cli.left_behind_lightness, &mut self.auto_colors.left_behind_lightness),
+        ] {
+            match lightness {
+                Some(lightness) if (-1.0..=1.0).contains(&lightness) => *field = lightness,
+                Some(lightness) => {
+                    return Err(Error::InvalidColor(format!(
+                        "lightness change {lightness} is out of range, use value from -1.0 to 1.0"
+                    )))
+                }
+                None => {}
+            }
+        }
+
         let color_trail = match cli.color {
-            Some(ref color_str) => match color_str.parse::<u8>() {
-                Ok(color) => Color::Palette(color),
-                Err(_) => panic!("Incorrect value for color provided: {color_str}"),
-            },
+            // random color is picked for each faller, see [ColorMode::Random]
+            Some(ref color_str) if color_str == "rnd" => self.colors.trail.clone(),
+            Some(ref color_str) => Color::parse(color_str)?,
             None => self.colors.trail.clone(),
         };
         let color_trail = match cli.color_rgb {
-            Some(ref color_str) => {
-                let colors_str: Vec<_> = color_str.split(',').collect();
-                if colors_str.len() != 3 {
-                    panic!("RGB color needs to be specified using following syntax: r,g,b e.g.: 128,128,255");
-                }
-                let colors_int: Vec<u8> = colors_str
-                    .iter()
-                    .map(|s| s.parse().expect("Cannot convert color value to string"))
-                    .collect();
-                Color::rgb_from_vec(colors_int)
-            }
+            Some(ref color_str) => Color::parse_rgb(color_str)?,
             None => color_trail,
         };
         let colors = if cli.color_rgb.is_some() || cli.color.is_some() {
             Colors {
-                head: color_trail.get_auto_head_color(),
-                left_behind: color_trail.get_auto_left_behind_color(),
-                trail: color_trail.clone(),
+                gradient: self.colors.gradient.clone(),
+                background: self.colors.background.clone(),
+                ..Colors::from_trail(color_trail.clone(), &self.auto_colors)
             }
         } else {
             self.colors.clone()
         };
         self.colors = colors;
+        if cli.color.as_deref() == Some("rnd") {
+            self.color_mode = ColorMode::parse("random")?;
+        } else if cli.color.is_some() || cli.color_rgb.is_some() {
+            // explicitly selected color should not be overridden by color mode from config file
+            self.color_mode = ColorMode::Fixed;
+        }
+        if let Some(ref mode) = cli.color_mode {
+            self.color_mode = ColorMode::parse(mode)?;
+        }
+        if let Some(ref stops) = cli.gradient {
+            self.colors.gradient = Some(Gradient::parse(stops, cli.gradient_easing)?);
+        }
+        if let Some(ref mut gradient) = self.colors.gradient {
+            gradient.validate()?;
+        }
+        if let Some(ref background) = cli.background {
+            self.colors.background = Some(Color::parse(background)?);
+        }
 
         let no_fallers = match cli.no_fallers {
             Some(no) => match no {
@@ -117,22 +308,77 @@ impl Config {
             None => self.no_fallers,
         };
         self.no_fallers = no_fallers;
+        if let Some(ref speed) = cli.speed {
+            self.speed = Speed::parse(speed)?;
+        }
+        if let Some(fps) = cli.fps {
+            self.fps = fps;
+        }
+        if !(1..=MAX_FPS).contains(&self.fps) {
+            return Err(Error::ConfigParse(format!(
+                "{} frames per second is out of range, use value from 1 to {MAX_FPS}",
+                self.fps
+            )));
+        }
+        if let Some(time_scale) = cli.time_scale {
+            self.time_scale = time_scale;
+        }
+        if !(MIN_TIME_SCALE..=MAX_TIME_SCALE).contains(&self.time_scale) {
+            return Err(Error::ConfigParse(format!(
+                "time scale {} is out of range, use value from {MIN_TIME_SCALE} to {MAX_TIME_SCALE}",
+                self.time_scale
+            )));
+        }
 
+        if cli.charset.is_some() {
+            self.charset = cli.charset.clone();
+        }
+        if let Some(ref spec) = self.charset {
+            self.chars_to_use = charset::parse(spec)?;
+        }
         let chars_to_use = match cli.chars_to_use {
-            Some(str) => str,
+            Some(ref str) => str.clone(),
             None => self.chars_to_use.clone(),
         };
         self.chars_to_use = chars_to_use;
+        // control characters would be sent to terminal as they are and break the screen
+        self.chars_to_use.retain(|ch| !ch.is_control());
+        if self.chars_to_use.is_empty() {
+            return Err(Error::ConfigParse("chars to use cannot be empty".into()));
+        }
+
+        if let Some(duration) = cli.decay {
+            self.decay.duration = duration;
+        }
+        if let Some(mutation) = cli.decay_mutation {
+            self.decay.mutation = mutation;
+        }
+        if !(self.decay.duration.is_finite() && self.decay.duration >= 0.0) {
+            return Err(Error::ConfigParse(format!(
+                "decay duration {} needs to be a number of seconds, 0 or more",
+                self.decay.duration
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.decay.mutation) {
+            return Err(Error::ConfigParse(format!(
+                "decay mutation probability {} is out of range, use value from 0.0 to 1.0",
+                self.decay.mutation
+            )));
+        }
 
-        let message = cli.message.clone().map(|message_text| Message {
-            position: PositionType::Center(CenteredPosition::new(
-                &size,
-                &TextType::StaticString(message_text.clone()),
-            )),
-            text: TextType::StaticString(message_text),
-            color: color_trail.clone(),
-            bounds: size,
-        });
+        let message = match cli.message.clone() {
+            Some(message_text) => Some(Message {
+                position: PositionType::Center(CenteredPosition::new(
+                    &size,
+                    &TextType::StaticString(message_text.clone()),
+                )?),
+                text: TextType::StaticString(message_text),
+                color: color_trail.clone(),
+                bounds: size,
+                background: self.message.as_ref().and_then(|message| message.background.clone()),
+            }),
+            None => None,
+        };
         // New message is present use it
         if message.is_some() {
             if message.clone().unwrap().text.to_string().is_empty() {
@@ -145,36 +391,65 @@ impl Config {
             self.message = self.message.clone();
         }
         if let Some(ref mut message) = self.message {
+            if message.text.to_string().chars().any(char::is_control) {
+                return Err(Error::ConfigParse("message cannot contain control characters".into()));
+            }
             message.bounds = size;
-            message.update_position();
+            message.update_position()?;
+            if let Some(ref background) = cli.message_background {
+                message.background = Some(Color::parse(background)?);
+            }
+        }
+
+        if cli.seed.is_some() {
+            self.seed = cli.seed;
         }
+        // bindings are used only in terminal, but mistakes should be reported also when printing config
+        KeyBindings::new(&self.keys)?;
+        Ok(())
     }
 }
 
+/// Maximum supported frames per second
+const MAX_FPS: u32 = 240;
+
+/// Minimum supported time scale
+const MIN_TIME_SCALE: f32 = 0.01;
+
+/// Maximum supported time scale
+const MAX_TIME_SCALE: f32 = 100.0;
+
+/// Screen size used when it cannot be read from terminal
+const FALLBACK_SCREEN_SIZE: (u16, u16) = (80, 24);
+
 impl Default for Config {
     fn default() -> Self {
-        let default_size = terminal_size().expect("Cannot get terminal size!");
+        let default_size = terminal_size().unwrap_or(FALLBACK_SCREEN_SIZE);
         let screen_size = Position::new(default_size.0, default_size.1);
         let message_text = TextType::StaticString(format!("   ruMatrix {VERSION}   "));
+        let classic = Theme::builtin("classic").expect("classic theme is built-in");
         let message = Message::new_centered_or_none(
             screen_size,
             message_text,
-            Color::RGB {
-                r: 41,
-                g: 194,
-                b: 148,
-            },
+            classic.message_color.expect("classic theme has message color"),
         );
         Self {
             screen_size,
-            colors: Colors {
-                trail: Color::RGB { r: 127, g: 222, b: 90 },
-                head: Color::RGB { r: 100, g: 150, b: 110 },
-                left_behind: Color::RGB { r: 13, g: 89, b: 30 },
-            },
+            colors: classic.colors,
+            auto_colors: AutoColors::default(),
+            color_mode: ColorMode::Fixed,
             no_fallers: 50,
-            chars_to_use: "abcdefghijklmnopqrstuwvxyzABCDEFGHIJKLMNOPQRSTUWVXYZ0123456789!@$%^&*()_+|{}[]<>?!~\\/.,:;".into(),
+            speed: Speed::default(),
+            fps: 30,
+            time_scale: 1.0,
+            chars_to_use: charset::builtin("default").expect("default charset is built-in"),
+            charset: None,
+            decay: Decay::default(),
             message,
+            seed: None,
+            theme: None,
+            keys: BTreeMap::new(),
+            file_settings: FileSettings::default(),
         }
     }
 }
@@ -187,13 +462,27 @@ impl Default for Config {
 pub struct Cli {
     /// Force width (x) of the screen
     #[arg(long, short = 'x')]
-    size_x: Option<u16>,
+    pub size_x: Option<u16>,
 
     /// Force height (y) of the screen
     #[arg(long, short = 'y')]
-    size_y: Option<u16>,
+    pub size_y: Option<u16>,
+
+    /// Use named theme, built-in or <NAME>.yaml from themes directory, see --list-themes;
+    /// colors, chars and message set in config file or on command line win over the theme
+    #[arg(long, short = 't')]
+    theme: Option<String>,
 
-    /// Select color (1-8 inclusive) of fallers or 'rnd' for random
+    /// Directory with user themes, ~/.config/rumatrix/themes by default
+    #[arg(long)]
+    pub themes_dir: Option<PathBuf>,
+
+    /// List available themes with their preview
+    #[arg(long)]
+    pub list_themes: bool,
+
+    /// Select color of fallers or 'rnd' for random: palette number (0-255, 16-255 need 256-color terminal),
+    /// #rrggbb, rgb(r,g,b), hsl(h,s%,l%) or CSS color name
     #[arg(long, short = 'c')]
     color: Option<String>,
 
@@ -201,14 +490,71 @@ pub struct Cli {
     #[arg(long, short = 'C')]
     color_rgb: Option<String>,
 
+    /// Change of perceived lightness (-1.0 - 1.0) between trail and head colors derived from --color
+    #[arg(long, allow_negative_numbers = true)]
+    head_lightness: Option<f32>,
+
+    /// Change of perceived lightness (-1.0 - 1.0) between trail and left behind colors derived from --color
+    #[arg(long, allow_negative_numbers = true)]
+    left_behind_lightness: Option<f32>,
+
+    /// How colors of fallers are chosen: fixed, random[:color;color...] (same as --color rnd),
+    /// hue-cycle[:period in frames] or rainbow, colors use the same syntax as --color
+    #[arg(long)]
+    color_mode: Option<String>,
+
+    /// Gradient of the trail from head to its end, as position:color pairs separated by ';',
+    /// position in percent (0-100), colors use the same syntax as --color e.g. 0:#c8ffc8;100:darkgreen
+    #[arg(long, short = 'g')]
+    gradient: Option<String>,
+
+    /// Easing used between colors of the gradient
+    #[arg(long, value_enum, default_value_t = Easing::Linear, requires = "gradient")]
+    gradient_easing: Easing,
+
+    /// Background color of the screen, uses the same syntax as --color (default: terminal background)
+    #[arg(long)]
+    background: Option<String>,
+
+    /// Background color of the message, uses the same syntax as --color (default: screen background)
+    #[arg(long)]
+    message_background: Option<String>,
+
     /// Number of fallers
     #[arg(long, short = 'n')]
     no_fallers: Option<usize>,
 
+    /// Speed of fallers in cells per second, as min-max range (each faller gets random speed from it)
+    /// or single value e.g. 8.5-30, speed is limited to one cell per frame
+    #[arg(long, short = 's')]
+    speed: Option<String>,
+
+    /// Frames per second (1-240), when terminal cannot keep up frames are dropped instead of slowing down
+    #[arg(long)]
+    fps: Option<u32>,
+
+    /// How fast time passes (0.01-100), e.g. 0.5 for slow motion or 2 for double speed
+    #[arg(long)]
+    time_scale: Option<f32>,
+
     /// Chars to use, if not specified use default list
     #[arg(long, short = 'u')]
     chars_to_use: Option<String>,
 
+    /// Charsets to use instead of --chars-to-use, comma separated: default, katakana, binary, hex, digits,
+    /// braille, runic, box-drawing, greek, emoji, Unicode ranges (U+30A0-U+30FF) or file:<path>
+    #[arg(long)]
+    charset: Option<String>,
+
+    /// Number of seconds after which characters left behind by fallers fade into the background,
+    /// 0 keeps them until the screen is cleared
+    #[arg(long)]
+    decay: Option<f32>,
+
+    /// Probability (0.0 - 1.0) that character left behind changes into a random one in each frame
+    #[arg(long)]
+    decay_mutation: Option<f64>,
+
     /// Message to show on the screen (default: no message)
     #[arg(long = "msg", short = 'm')]
     message: Option<String>,
@@ -218,6 +564,10 @@ pub struct Cli {
     #[arg(long = "date", short = 'd')]
     date_format: Option<String>,
 
+    /// Seed for random number generator, runs with the same seed and screen size look the same
+    #[arg(long)]
+    seed: Option<u64>,
+
     /// Print current configuration as YAML - do not include default values
     #[arg(long = "print-config")]
     pub print_config: bool,
@@ -229,4 +579,145 @@ pub struct Cli {
     /// Load config YAML file from path
     #[arg(long = "config-file", short = 'f')]
     pub config_file: Option<String>,
+
+    /// Run without terminal and write rendered frames to stdout or --output file,
+    /// use --size-x and --size-y to set size of the frames
+    #[arg(long, requires = "frames")]
+    pub headless: bool,
+
+    /// Number of frames to render in headless mode
+    #[arg(long, requires = "headless")]
+    pub frames: Option<usize>,
+
+    /// Format of frames written in headless mode
+    #[arg(long, value_enum, default_value_t = DumpFormat::Text, requires = "headless")]
+    pub format: DumpFormat,
+
+    /// Write only the last frame in headless mode
+    #[arg(long, requires = "headless")]
+    pub last_frame_only: bool,
+
+    /// File to write frames to in headless mode instead of stdout
+    #[arg(long, short = 'o', requires = "headless")]
+    pub output: Option<String>,
+
+    /// Record session to asciicast v2 file, works also in headless mode
+    #[arg(long)]
+    pub record: Option<String>,
+
+    /// Listen for commands on Unix domain socket at this path, one command per line:
+    /// message [text], message-color <color>, color <color>, fallers <number>, pause, resume, quit, status
+    #[arg(long)]
+    pub control_socket: Option<PathBuf>,
+
+    /// Export frames rendered in headless mode as animated GIF file, its font has only ASCII characters
+    #[arg(long, requires = "headless")]
+    pub gif: Option<String>,
+
+    /// Export frames rendered in headless mode as PNG files named <PNG>00001.png, <PNG>00002.png...,
+    /// their font has only ASCII characters
+    #[arg(long, requires = "headless")]
+    pub png: Option<String>,
+
+    /// Export frames rendered in headless mode as self-contained HTML page replaying them
+    #[arg(long, requires = "headless")]
+    pub html: Option<String>,
+
+    /// Export frames rendered in headless mode as animated SVG image
+    #[arg(long, requires = "headless")]
+    pub svg: Option<String>,
+
+    /// Number of colors terminal can display, RGB colors are converted to the closest available ones,
+    /// detected from COLORTERM and TERM environment variables by default
+    #[arg(long, value_enum)]
+    pub color_depth: Option<ColorDepth>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Parse command line `args` (without program name)
+    fn cli(args: &[&str]) -> Cli {
+        Cli::parse_from(std::iter::once("rumatrix").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn empty_chars_to_use_are_rejected() {
+        let mut config = Config::default();
+        assert!(matches!(config.apply_cli(&cli(&["-u", ""])), Err(Error::ConfigParse(_))));
+
+        let mut config: Config = serde_yaml::from_str("chars_to_use: ''").unwrap();
+        assert!(matches!(config.apply_cli(&cli(&[])), Err(Error::ConfigParse(_))));
+
+        let mut config = Config::default();
+        config.apply_cli(&cli(&["-u", "ab"])).unwrap();
+        assert_eq!(config.chars_to_use(), "ab");
+    }
+
+    #[test]
+    fn control_characters_are_not_used() {
+        let mut config = Config::default();
+        config.apply_cli(&cli(&["-u", "a\tb\n\x1b"])).unwrap();
+        assert_eq!(config.chars_to_use(), "ab");
+
+        let mut config: Config = serde_yaml::from_str("chars_to_use: \"\\n\\t\"").unwrap();
+        assert!(matches!(config.apply_cli(&cli(&[])), Err(Error::ConfigParse(_))));
+
+        let mut config = Config::default();
+        let result = config.apply_cli(&cli(&["-x", "40", "--msg", "WAKE\nUP"]));
+        assert!(matches!(result, Err(Error::ConfigParse(_))));
+    }
+    #[test]
+    fn time_scale_is_bounded() {
+        for time_scale in ["1e-30", "0.001", "0", "-1", "101", "1e30", "inf", "NaN"] {
+            let mut config = Config::default();
+            let result = config.apply_cli(&cli(&[&format!("--time-scale={time_scale}")]));
+            assert!(matches!(result, Err(Error::ConfigParse(_))), "{time_scale}");
+        }
+        for time_scale in ["0.01", "100"] {
+            let mut config = Config::default();
+            config.apply_cli(&cli(&[&format!("--time-scale={time_scale}")])).unwrap();
+            config.frame_duration();
+        }
+    }
+
+    #[test]
+    fn gradient_from_file_is_sorted() {
+        let yaml = "colors:\n  trail: 2\n  head: 10\n  left_behind: 2\n  gradient:\n    stops:\n    \
+                    - {position: 100, color: '#000000'}\n    - {position: 0, color: '#ffffff'}\n";
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.apply_cli(&cli(&[])).unwrap();
+        let gradient = config.colors().gradient.as_ref().unwrap();
+        let positions: Vec<u8> = gradient.stops.iter().map(|stop| stop.position).collect();
+        assert_eq!(positions, [0, 100]);
+
+        let yaml = yaml.replace("position: 100", "position: 150");
+        let mut config: Config = serde_yaml::from_str(&yaml).unwrap();
+        assert!(matches!(config.apply_cli(&cli(&[])), Err(Error::InvalidColor(_))));
+    }
+
+    #[test]
+    fn config_file_settings_win_over_theme() {
+        let path = std::env::temp_dir().join(format!("rumatrix-theme-config-{}.yaml", std::process::id()));
+        let yaml = "theme: amber\nchars_to_use: xyz\ncolors:\n  trail: 1\n  head: 2\n  left_behind: 3\n";
+        fs::write(&path, yaml).unwrap();
+        let path_str = path.to_str().unwrap();
+        let amber = Theme::builtin("amber").unwrap();
+
+        let config = Config::load(Some(path_str), &cli(&[])).unwrap();
+        assert_eq!(config.colors().trail, Color::Palette(1));
+        assert_eq!(config.chars_to_use(), "xyz");
+        assert_eq!(config.message().as_ref().unwrap().color, amber.message_color.unwrap());
+
+        // theme from command line does not override config file either, other command line options do
+        let config = Config::load(Some(path_str), &cli(&["--theme", "ice", "-u", "ab"])).unwrap();
+        assert_eq!(config.colors().trail, Color::Palette(1));
+        assert_eq!(config.chars_to_use(), "ab");
+
+        fs::write(&path, "theme: amber\n").unwrap();
+        let config = Config::load(Some(path_str), &cli(&[])).unwrap();
+        assert_eq!(config.colors(), &amber.colors);
+        fs::remove_file(&path).unwrap();
+    }
 }
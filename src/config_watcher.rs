@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often [ConfigWatcher] checks the config file
+pub const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches config file for changes by checking its modification time
+pub struct ConfigWatcher {
+    /// Path of watched file
+    path: PathBuf,
+    /// Minimum time between checks
+    interval: Duration,
+    /// Modification time seen during the last check, [None] if file could not be read
+    modified: Option<SystemTime>,
+    /// When file was checked last time
+    last_check: Instant,
+}
+
+impl ConfigWatcher {
+    /// Watch file at `path`, checking it at most every `interval`
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        let path = path.into();
+        Self {
+            modified: Self::modified(&path),
+            path,
+            interval,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// Modification time of file at `path`
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Was the file changed (or removed) since it was checked last time
+    ///
+    /// Cheap to call in every frame, file is checked only when interval passed.
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < self.interval {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let modified = Self::modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use std::fs::File;
+
+    #[test]
+    fn detects_modification() {
+        let path = env::temp_dir().join(format!("rumatrix-watch-{}.yaml", std::process::id()));
+        fs::write(&path, "no_fallers: 10\n").unwrap();
+        let mut watcher = ConfigWatcher::new(&path, Duration::ZERO);
+        assert!(!watcher.changed());
+
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+    }
+
+    #[test]
+    fn checks_only_after_interval() {
+        let path = env::temp_dir().join(format!("rumatrix-watch-interval-{}.yaml", std::process::id()));
+        let mut watcher = ConfigWatcher::new(&path, Duration::from_secs(3600));
+        fs::write(&path, "no_fallers: 10\n").unwrap();
+        assert!(!watcher.changed());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,318 @@
+use crate::colors::Color;
+use crate::error::Error;
+use crate::faller_adder::FallerAdder;
+use crate::message::{Message, TextType};
+use crate::position::{CenteredPosition, PositionTrait, PositionType};
+use crate::timestep::Timestep;
+use serde_json::json;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+/// Longest accepted command, client sending longer line is disconnected
+const MAX_LINE_LENGTH: usize = 4096;
+
+/// Command sent to [ControlSocket], one per line
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    /// `message [text]` shows centered message with `text`, without text the message is hidden
+    Message(Option<String>),
+    /// `message-color <color>` changes color of the message
+    MessageColor(Color),
+    /// `color <color>` changes color of all fallers, head and left behind colors are derived from it
+    Color(Color),
+    /// `fallers <number>` changes maximum number of fallers
+    Fallers(usize),
+    /// `pause` stops the animation
+    Pause,
+    /// `resume` continues paused animation
+    Resume,
+    /// `quit` exits the program
+    Quit,
+    /// `status` returns current state as JSON
+    Status,
+}
+
+impl Command {
+    /// Parse single `line` sent to [ControlSocket], colors use the same syntax as `--color`
+    pub fn parse(line: &str) -> Result<Self, Error> {
+        let line = line.trim();
+        let (name, argument) = match line.split_once(' ') {
+            Some((name, argument)) => (name, Some(argument.trim())),
+            None => (line, None),
+        };
+        let command = match (name, argument) {
+            ("message", text) => Command::Message(text.map(str::to_owned)),
+            ("message-color", Some(color)) => Command::MessageColor(Color::parse(color)?),
+            ("color", Some(color)) => Command::Color(Color::parse(color)?),
+            ("fallers", Some(number)) => Command::Fallers(number.parse().map_err(|_| {
+                Error::ControlSocket(format!("number of fallers '{number}' is not a number"))
+            })?),
+            ("pause", None) => Command::Pause,
+            ("resume", None) => Command::Resume,
+            ("quit", None) => Command::Quit,
+            ("status", None) => Command::Status,
+            _ => return Err(Error::ControlSocket(format!("unknown command '{line}'"))),
+        };
+        Ok(command)
+    }
+
+    /// Execute command changing simulation of `faller_adder` and `timestep`, returns response sent to the client
+    pub fn execute(self, faller_adder: &mut FallerAdder, timestep: &mut Timestep) -> Result<String, Error> {
+        match self {
+            Command::Message(Some(text)) => {
+                if text.chars().any(char::is_control) {
+                    return Err(Error::ControlSocket("message cannot contain control characters".into()));
+                }
+                let message = {
+                    let config = faller_adder.config.borrow();
+                    let size = *config.screen_size();
+                    let text = TextType::StaticString(text);
+                    let (color, background) = match config.message() {
+                        Some(message) => (message.color.clone(), message.background.clone()),
+                        None => (config.colors().trail.clone(), None),
+                    };
+                    Message {
+                        position: PositionType::Center(CenteredPosition::new(&size, &text)?),
+                        bounds: size,
+                        text,
+                        color,
+                        background,
+                    }
+                };
+                faller_adder.set_message(Some(message));
+            }
+            Command::Message(None) => faller_adder.set_message(None),
+            Command::MessageColor(color) => {
+                let message = faller_adder.config.borrow().message().clone();
+                match message {
+                    Some(message) => faller_adder.set_message(Some(Message { color, ..message })),
+                    None => return Err(Error::ControlSocket("there is no message to change color of".into())),
+                }
+            }
+            Command::Color(color) => faller_adder.set_trail_color(color),
+            Command::Fallers(number) => faller_adder.config.borrow_mut().set_no_fallers(number),
+            Command::Pause => timestep.set_paused(true),
+            Command::Resume => timestep.set_paused(false),
+            Command::Quit => crate::request_quit(),
+            Command::Status => {
+                let config = faller_adder.config.borrow();
+                let status = json!({
+                    "paused": timestep.is_paused(),
+                    "time_scale": timestep.time_scale(),
+                    "fps": config.fps(),
+                    "fallers": config.no_fallers(),
+                    "active_fallers": faller_adder.falling_chars.borrow().len(),
+                    "screen": {
+                        "width": config.screen_size().x(),
+                        "height": config.screen_size().y(),
+                    },
+                    "color": config.colors().trail,
+                    "message": config.message().as_ref().map(|message| message.text.to_string()),
+                    "message_color": config.message().as_ref().map(|message| &message.color),
+                });
+                return Ok(status.to_string());
+            }
+        }
+        Ok("ok".into())
+    }
+}
+
+/// Connected client of [ControlSocket]
+struct Client {
+    /// Non-blocking connection
+    stream: UnixStream,
+    /// Received bytes which do not form complete line yet
+    buffer: Vec<u8>,
+}
+
+/// Unix domain socket accepting [Command]s, which control running program
+///
+/// Each line sent by a client is one command, it is answered with one line: `ok`, `error: <reason>`
+/// or JSON for [Command::Status]. Socket file is removed when [ControlSocket] is dropped.
+pub struct ControlSocket {
+    /// Path of the socket file
+    path: PathBuf,
+    /// Non-blocking listener accepting clients
+    listener: UnixListener,
+    /// Connected clients
+    clients: Vec<Client>,
+}
+
+impl ControlSocket {
+    /// Listen on socket at `path`
+    ///
+    /// Socket left behind by an instance which did not exit cleanly is replaced, any other existing file is
+    /// an error
+    pub fn bind(path: &Path) -> Result<Self, Error> {
+        let stale = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket())
+            && UnixStream::connect(path).is_err();
+        if stale {
+            fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot listen on '{}': {e}", path.display())))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            path: path.to_owned(),
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    /// Accept new clients and execute all complete commands received since the last call, never blocks
+    pub fn poll(&mut self, faller_adder: &mut FallerAdder, timestep: &mut Timestep) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push(Client {
+                    stream,
+                    buffer: Vec::new(),
+                });
+            }
+        }
+        self.clients.retain_mut(|client| {
+            let mut connected = true;
+            let mut chunk = [0; 1024];
+            loop {
+                match client.stream.read(&mut chunk) {
+                    Ok(0) => {
+                        connected = false;
+                        break;
+                    }
+                    Ok(length) => client.buffer.extend_from_slice(&chunk[..length]),
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(_) => return false,
+                }
+            }
+            // client which closed its side still gets responses to commands it sent before
+            while let Some(end) = client.buffer.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = client.buffer.drain(..=end).collect();
+                let response = match Command::parse(&String::from_utf8_lossy(&line)) {
+                    Ok(command) => command.execute(faller_adder, timestep),
+                    Err(error) => Err(error),
+                };
+                let response = response.unwrap_or_else(|error| format!("error: {error}"));
+                if writeln!(client.stream, "{response}").is_err() {
+                    return false;
+                }
+            }
+            connected && client.buffer.len() <= MAX_LINE_LENGTH
+        });
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use crate::position::Position;
+    use crate::rng::new_rng;
+    use std::cell::RefCell;
+    use std::env;
+    use std::io::{BufRead, BufReader};
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    fn faller_adder() -> FallerAdder {
+        let mut config = Config::default();
+        config.set_screen_size(Position::new(40, 10));
+        FallerAdder::new(Rc::new(RefCell::new(config)), new_rng(Some(1)))
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            Command::parse("message BUILD FAILED\n").unwrap(),
+            Command::Message(Some("BUILD FAILED".into()))
+        );
+        assert_eq!(Command::parse("message").unwrap(), Command::Message(None));
+        assert_eq!(Command::parse("color red").unwrap(), Command::Color(Color::RGB { r: 255, g: 0, b: 0 }));
+        assert_eq!(Command::parse("fallers 20").unwrap(), Command::Fallers(20));
+        assert_eq!(Command::parse("status").unwrap(), Command::Status);
+        assert!(Command::parse("fallers many").is_err());
+        assert!(Command::parse("color").is_err());
+        assert!(Command::parse("pause now").is_err());
+        assert!(Command::parse("dance").is_err());
+    }
+
+    #[test]
+    fn execute_commands() {
+        let mut faller_adder = faller_adder();
+        faller_adder.probability_to_add = 1.0;
+        faller_adder.add_and_retire().unwrap();
+        let mut timestep = Timestep::new(Duration::from_millis(10), 1.0);
+        let red = Color::RGB { r: 255, g: 0, b: 0 };
+        for command in [
+            Command::Message(Some("BUILD FAILED".into())),
+            Command::MessageColor(red.clone()),
+            Command::Color(red.clone()),
+            Command::Fallers(20),
+            Command::Pause,
+        ] {
+            assert_eq!(command.execute(&mut faller_adder, &mut timestep).unwrap(), "ok");
+        }
+        {
+            let config = faller_adder.config.borrow();
+            let message = config.message().as_ref().unwrap();
+            assert_eq!(message.text.to_string(), "BUILD FAILED");
+            assert_eq!(message.color, red);
+            assert_eq!(message.position.x(), 14);
+            assert_eq!(config.colors().trail, red);
+            let falling_chars = faller_adder.falling_chars.borrow();
+            assert!(!falling_chars.is_empty());
+            assert!(falling_chars.iter().all(|f| f.colors().trail == red));
+        }
+        let status = Command::Status.execute(&mut faller_adder, &mut timestep).unwrap();
+        let status: serde_json::Value = serde_json::from_str(&status).unwrap();
+        assert_eq!(status["paused"], true);
+        assert_eq!(status["fallers"], 20);
+        assert_eq!(status["color"], "#ff0000");
+        assert_eq!(status["message"], "BUILD FAILED");
+
+        let control = Command::Message(Some("BUILD\tFAILED".into()));
+        assert!(control.execute(&mut faller_adder, &mut timestep).is_err());
+        let too_long = Command::Message(Some("x".repeat(41)));
+        assert!(too_long.execute(&mut faller_adder, &mut timestep).is_err());
+        Command::Message(None).execute(&mut faller_adder, &mut timestep).unwrap();
+        assert!(Command::MessageColor(red).execute(&mut faller_adder, &mut timestep).is_err());
+    }
+
+    #[test]
+    fn commands_over_socket() {
+        let path = env::temp_dir().join(format!("rumatrix-control-{}.sock", std::process::id()));
+        let mut socket = ControlSocket::bind(&path).unwrap();
+        let mut faller_adder = faller_adder();
+        let mut timestep = Timestep::new(Duration::from_millis(10), 1.0);
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"fallers 7\nstatus\nfly\n").unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        socket.poll(&mut faller_adder, &mut timestep);
+
+        let lines: Vec<String> = BufReader::new(client).lines().map(Result::unwrap).collect();
+        assert_eq!(lines[0], "ok");
+        assert!(lines[1].contains("\"fallers\":7"));
+        assert_eq!(lines[2], "error: incorrect command: unknown command 'fly'");
+        assert!(socket.clients.is_empty());
+
+        drop(socket);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn bind_fails_on_existing_file() {
+        let path = env::temp_dir().join(format!("rumatrix-control-file-{}", std::process::id()));
+        fs::write(&path, "").unwrap();
+        assert!(matches!(ControlSocket::bind(&path), Err(Error::Io(_))));
+        fs::remove_file(&path).unwrap();
+    }
+}
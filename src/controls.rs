@@ -0,0 +1,700 @@
+use crate::colors::Color;
+use crate::config::Config;
+use crate::error::Error;
+use crate::faller_adder::FallerAdder;
+use crate::frame_buffer::{Cell, FrameBuffer};
+use crate::message::Message;
+use crate::position::{Position, PositionTrait};
+use crate::residue::Residue;
+use crate::theme::{Theme, BUILTIN_THEMES};
+use crate::timestep::Timestep;
+use crate::unicode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use termion::event::Key;
+
+/// Action which can be triggered by a key while the program runs, see [KeyBindings]
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Exit the program
+    Quit,
+    /// Clear the screen and characters left behind
+    Clear,
+    /// Pause or resume the animation
+    Pause,
+    /// Advance paused animation by a single frame
+    Step,
+    /// Make time pass faster
+    Faster,
+    /// Make time pass slower
+    Slower,
+    /// Increase maximum number of fallers
+    MoreFallers,
+    /// Decrease maximum number of fallers
+    FewerFallers,
+    /// Switch to the next built-in theme
+    NextTheme,
+    /// Hide or show the message
+    ToggleMessage,
+    /// Show or hide help with key bindings
+    Help,
+}
+
+impl Action {
+    /// All actions in order in which they are listed in help
+    pub const ALL: [Action; 11] = [
+        Action::Quit,
+        Action::Clear,
+        Action::Pause,
+        Action::Step,
+        Action::Faster,
+        Action::Slower,
+        Action::MoreFallers,
+        Action::FewerFallers,
+        Action::NextTheme,
+        Action::ToggleMessage,
+        Action::Help,
+    ];
+
+    /// Short description shown in help
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Clear => "clear screen",
+            Action::Pause => "pause / resume",
+            Action::Step => "single step while paused",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::MoreFallers => "more fallers",
+            Action::FewerFallers => "fewer fallers",
+            Action::NextTheme => "next theme",
+            Action::ToggleMessage => "show / hide message",
+            Action::Help => "show / hide this help",
+        }
+    }
+}
+
+/// Keys bound to [Action]s unless configured otherwise, see [parse_key] for key names
+pub const DEFAULT_KEY_BINDINGS: [(&str, Action); 16] = [
+    ("q", Action::Quit),
+    // in raw mode CTRL+C does not send SIGINT, it arrives as a key
+    ("ctrl+c", Action::Quit),
+    ("c", Action::Clear),
+    ("space", Action::Pause),
+    ("n", Action::Step),
+    ("+", Action::Faster),
+    ("up", Action::Faster),
+    ("-", Action::Slower),
+    ("down", Action::Slower),
+    ("]", Action::MoreFallers),
+    ("right", Action::MoreFallers),
+    ("[", Action::FewerFallers),
+    ("left", Action::FewerFallers),
+    ("t", Action::NextTheme),
+    ("m", Action::ToggleMessage),
+    ("?", Action::Help),
+];
+
+/// Names of keys which are not characters, together with [Key]s they stand for
+const NAMED_KEYS: [(&str, Key); 16] = [
+    ("space", Key::Char(' ')),
+    ("enter", Key::Char('\n')),
+    ("tab", Key::Char('\t')),
+    ("backtab", Key::BackTab),
+    ("esc", Key::Esc),
+    ("backspace", Key::Backspace),
+    ("delete", Key::Delete),
+    ("insert", Key::Insert),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("up", Key::Up),
+    ("down", Key::Down),
+    ("left", Key::Left),
+    ("right", Key::Right),
+];
+
+/// Parse key `name`: single character (`q`, `Q`, `?`), `ctrl+<char>`, `alt+<char>`, `f1` - `f12` or one of
+/// space, enter, tab, backtab (shift+tab), esc, backspace, delete, insert, home, end, pageup, pagedown, up, down,
+/// left and right
+pub fn parse_key(name: &str) -> Result<Key, Error> {
+    let error = || Error::ConfigParse(format!("unknown key '{name}'"));
+    let single_char = |text: &str| {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Some(ch),
+            _ => None,
+        }
+    };
+    if let Some(ch) = single_char(name) {
+        return Ok(Key::Char(ch));
+    }
+    if let Some((modifier, rest)) = name.split_once('+') {
+        return match (modifier.to_lowercase().as_str(), single_char(rest)) {
+            ("ctrl", Some(ch)) => Ok(Key::Ctrl(ch.to_ascii_lowercase())),
+            // alt keeps case of the character, alt+X is different key than alt+x
+            ("alt", Some(ch)) => Ok(Key::Alt(ch)),
+            ("shift", None) if rest.eq_ignore_ascii_case("tab") => Ok(Key::BackTab),
+            _ => Err(error()),
+        };
+    }
+    let lowercase = name.to_lowercase();
+    if let Some(number) = lowercase.strip_prefix('f').and_then(|number| number.parse::<u8>().ok()) {
+        return match number {
+            1..=12 => Ok(Key::F(number)),
+            _ => Err(error()),
+        };
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(named, _)| *named == lowercase)
+        .map(|(_, key)| *key)
+        .ok_or_else(error)
+}
+
+/// Name of `key` which can be parsed back with [parse_key]
+pub fn key_name(key: &Key) -> String {
+    if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, named)| named == key) {
+        return (*name).to_owned();
+    }
+    match key {
+        Key::Char(ch) => ch.to_string(),
+        Key::Ctrl(ch) => format!("ctrl+{ch}"),
+        Key::Alt(ch) => format!("alt+{ch}"),
+        Key::F(number) => format!("f{number}"),
+        other => format!("{other:?}").to_lowercase(),
+    }
+}
+
+/// Keys bound to [Action]s: [DEFAULT_KEY_BINDINGS] overridden by configured ones
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyBindings(Vec<(Key, Action)>);
+
+impl KeyBindings {
+    /// Create [KeyBindings] from defaults and `configured` key names mapped to actions, [None] unbinds the key
+    ///
+    /// Returns [Error::ConfigParse] if any key name is not known, see [parse_key]
+    pub fn new(configured: &BTreeMap<String, Option<Action>>) -> Result<Self, Error> {
+        let mut bindings: Vec<(Key, Action)> = DEFAULT_KEY_BINDINGS
+            .iter()
+            .map(|&(name, action)| parse_key(name).map(|key| (key, action)))
+            .collect::<Result<_, _>>()?;
+        for (name, action) in configured {
+            let key = parse_key(name)?;
+            bindings.retain(|(bound, _)| *bound != key);
+            if let Some(action) = action {
+                bindings.push((key, *action));
+            }
+        }
+        Ok(Self(bindings))
+    }
+
+    /// Get [Action] bound to `key`
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        self.0.iter().find(|(bound, _)| bound == key).map(|&(_, action)| action)
+    }
+
+    /// Names of keys bound to `action`
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        self.0
+            .iter()
+            .filter(|&&(_, bound)| bound == action)
+            .map(|(key, _)| key_name(key))
+            .collect()
+    }
+}
+
+/// Factor by which [Action::Faster] and [Action::Slower] change time scale
+const TIME_SCALE_FACTOR: f32 = 1.25;
+
+/// Minimum and maximum time scale reachable with [Action::Faster] and [Action::Slower]
+const TIME_SCALE_LIMITS: (f32, f32) = (0.1, 10.0);
+
+/// Colors of help text and its background
+const HELP_COLORS: (Color, Color) = (Color::Palette(15), Color::Palette(0));
+
+/// Colors of notice about an error and its background
+const ERROR_COLORS: (Color, Color) = (Color::Palette(15), Color::Palette(1));
+
+/// How long notice is shown, see [Controls::show_notice]
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
+
+/// Short notice shown at the bottom of the screen, e.g. result of config reload
+struct Notice {
+    /// Text of the notice
+    text: String,
+    /// Notice is about an error
+    error: bool,
+    /// When notice was shown for the first time
+    shown_at: Instant,
+}
+
+/// State of runtime controls, which change the running program in response to [Action]s
+pub struct Controls {
+    /// [KeyBindings] which trigger [Action]s
+    pub bindings: KeyBindings,
+    /// Help with key bindings is shown over the animation
+    pub show_help: bool,
+    /// Index of current theme in [BUILTIN_THEMES]
+    theme: usize,
+    /// Index of theme set in config, differs from `theme` after [Action::NextTheme]
+    configured_theme: usize,
+    /// Message hidden by [Action::ToggleMessage]
+    hidden_message: Option<Message>,
+    /// Configured background of the screen, used by themes without their own one
+    background: Option<Color>,
+    /// [Notice] shown at the bottom of the screen
+    notice: Option<Notice>,
+}
+
+impl Controls {
+    /// Create new [Controls] for program started with `config`
+    ///
+    /// Returns [Error::ConfigParse] if configured key bindings are incorrect
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        let theme = config
+            .theme()
+            .as_deref()
+            .and_then(|name| BUILTIN_THEMES.iter().position(|&builtin| builtin == name))
+            .unwrap_or(0);
+        Ok(Self {
+            bindings: KeyBindings::new(config.keys())?,
+            show_help: false,
+            theme,
+            configured_theme: theme,
+            hidden_message: None,
+            background: config.colors().background.clone(),
+            notice: None,
+        })
+    }
+
+    /// Use key bindings, theme and background of `config` reloaded from file, help and notice stay
+    ///
+    /// Theme picked with [Action::NextTheme] is applied to `config` again, unless the reloaded file changes
+    /// its theme. Message hidden by [Action::ToggleMessage] stays hidden: message of `config` is taken out of it
+    /// and shown again on the next toggle. Returns [Error::ConfigParse] if configured key bindings are incorrect.
+    pub fn reload(&mut self, config: &mut Config) -> Result<(), Error> {
+        let reloaded = Self::new(config)?;
+        let picked = self.theme != self.configured_theme && reloaded.theme == self.configured_theme;
+        let theme = if picked {
+            let mut theme = Theme::builtin(BUILTIN_THEMES[self.theme]).expect("built-in theme exists");
+            theme.colors.background = theme.colors.background.or_else(|| config.colors().background.clone());
+            config.apply_theme(&theme);
+            self.theme
+        } else {
+            reloaded.theme
+        };
+        let hidden_message = match self.hidden_message {
+            Some(_) => config.message().clone(),
+            None => None,
+        };
+        if hidden_message.is_some() {
+            config.set_message(None);
+        }
+        *self = Self {
+            show_help: self.show_help,
+            theme,
+            hidden_message,
+            notice: self.notice.take(),
+            ..reloaded
+        };
+        Ok(())
+    }
+
+    /// Show `text` at the bottom of the screen for a few seconds, `error` notices are highlighted
+    pub fn show_notice(&mut self, text: impl Into<String>, error: bool) {
+        self.notice = Some(Notice {
+            text: text.into(),
+            error,
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Do [Action] bound to `key`, if there is any
+    pub fn handle_key(
+        &mut self,
+        key: &Key,
+        frame: &mut FrameBuffer,
+        residue: &mut Residue,
+        faller_adder: &mut FallerAdder,
+        timestep: &mut Timestep,
+    ) {
+        if let Some(action) = self.bindings.action(key) {
+            self.apply(action, frame, residue, faller_adder, timestep);
+        }
+    }
+
+    /// Do `action`, changing running simulation
+    pub fn apply(
+        &mut self,
+        action: Action,
+        frame: &mut FrameBuffer,
+        residue: &mut Residue,
+        faller_adder: &mut FallerAdder,
+        timestep: &mut Timestep,
+    ) {
+        match action {
+            Action::Quit => crate::request_quit(),
+            Action::Clear => {
+                frame.clear();
+                residue.clear();
+            }
+            Action::Pause => timestep.set_paused(!timestep.is_paused()),
+            Action::Step => timestep.request_step(),
+            Action::Faster | Action::Slower => {
+                let factor = match action {
+                    Action::Faster => TIME_SCALE_FACTOR,
+                    _ => 1.0 / TIME_SCALE_FACTOR,
+                };
+                let time_scale = (timestep.time_scale() * factor).clamp(TIME_SCALE_LIMITS.0, TIME_SCALE_LIMITS.1);
+                timestep.set_time_scale(time_scale);
+            }
+            Action::MoreFallers | Action::FewerFallers => {
+                let mut config = faller_adder.config.borrow_mut();
+                let no_fallers = *config.no_fallers();
+                // change by tenth, so that it is noticeable with both few and many fallers
+                let change = (no_fallers / 10).max(1);
+                let no_fallers = match action {
+                    Action::MoreFallers => no_fallers.saturating_add(change),
+                    _ => no_fallers.saturating_sub(change),
+                };
+                config.set_no_fallers(no_fallers);
+            }
+            Action::NextTheme => {
+                self.theme = (self.theme + 1) % BUILTIN_THEMES.len();
+                let theme = Theme::builtin(BUILTIN_THEMES[self.theme]).expect("built-in theme exists");
+                frame.set_background(theme.colors.background.clone().or_else(|| self.background.clone()));
+                faller_adder.apply_theme(&theme);
+            }
+            Action::ToggleMessage => {
+                let shown = faller_adder.config.borrow().message().clone();
+                match shown {
+                    Some(message) => {
+                        self.hidden_message = Some(message);
+                        faller_adder.set_message(None);
+                    }
+                    None => faller_adder.set_message(self.hidden_message.take()),
+                }
+            }
+            Action::Help => self.show_help = !self.show_help,
+        }
+    }
+
+    /// Cells drawn over the animation on screen of `size`: help (if shown) and current notice
+    pub fn overlay(&self, faller_adder: &FallerAdder, timestep: &Timestep, size: Position) -> Vec<(Position, Cell)> {
+        let mut cells = Vec::new();
+        if self.show_help {
+            cells.extend(self.help_cells(faller_adder, timestep, size));
+        }
+        if let Some(ref notice) = self.notice {
+            if notice.shown_at.elapsed() < NOTICE_DURATION {
+                cells.extend(Self::notice_cells(notice, size));
+            }
+        }
+        cells
+    }
+
+    /// Cells of `notice` centered in the last row of screen of `size`, text which does not fit is cut
+    fn notice_cells(notice: &Notice, size: Position) -> Vec<(Position, Cell)> {
+        let (color, background) = if notice.error { ERROR_COLORS } else { HELP_COLORS };
+        let text = format!(" {} ", notice.text.replace(['\n', '\t'], " "));
+        let mut graphemes = Vec::new();
+        let mut width = 0;
+        for grapheme in unicode::graphemes(&text) {
+            let grapheme_width = unicode::grapheme_width(grapheme) as u16;
+            if width + grapheme_width > size.x() {
+                break;
+            }
+            graphemes.push((width, grapheme));
+            width += grapheme_width;
+        }
+        let left = (size.x() - width) / 2 + 1;
+        graphemes
+            .into_iter()
+            .map(|(x, grapheme)| {
+                (
+                    Position::new(left + x, size.y()),
+                    Cell::new(grapheme, color.clone()).with_background(Some(background.clone())),
+                )
+            })
+            .collect()
+    }
+
+    /// Cells of help with key bindings and current state, centered in screen of `size`
+    fn help_cells(&self, faller_adder: &FallerAdder, timestep: &Timestep, size: Position) -> Vec<(Position, Cell)> {
+        let mut lines = vec!["ruMatrix keys".to_owned(), String::new()];
+        let bound: Vec<(String, Action)> = Action::ALL
+            .iter()
+            .map(|&action| (self.bindings.keys_for(action).join(", "), action))
+            .filter(|(keys, _)| !keys.is_empty())
+            .collect();
+        let keys_width = bound.iter().map(|(keys, _)| unicode::width(keys)).max().unwrap_or(0);
+        lines.extend(bound.iter().map(|(keys, action)| {
+            let padding = " ".repeat(keys_width - unicode::width(keys));
+            format!("{padding}{keys}  {}", action.description())
+        }));
+        lines.push(String::new());
+        lines.push(format!(
+            "speed x{:.2}, {} fallers, {} theme{}",
+            timestep.time_scale(),
+            faller_adder.config.borrow().no_fallers(),
+            BUILTIN_THEMES[self.theme],
+            if timestep.is_paused() { ", paused" } else { "" }
+        ));
+
+        let width = lines.iter().map(|line| unicode::width(line)).max().unwrap_or(0) as u16 + 4;
+        let height = lines.len() as u16 + 2;
+        let left = size.x().saturating_sub(width) / 2 + 1;
+        let top = size.y().saturating_sub(height) / 2 + 1;
+        let (color, background) = HELP_COLORS;
+        let mut cells = Vec::with_capacity(usize::from(width) * usize::from(height));
+        let mut put = |x: u16, y: u16, symbol: &str| {
+            cells.push((
+                Position::new(left + x, top + y),
+                Cell::new(symbol, color.clone()).with_background(Some(background.clone())),
+            ));
+        };
+        // one line of padding above and below the text, two columns on the sides
+        for y in 0..height {
+            let line = y.checked_sub(1).and_then(|index| lines.get(usize::from(index)));
+            let text = format!("  {}", line.map_or("", String::as_str));
+            let mut x = 0;
+            for grapheme in unicode::graphemes(&text) {
+                put(x, y, grapheme);
+                x += unicode::grapheme_width(grapheme) as u16;
+            }
+            for x in x..width {
+                put(x, y, " ");
+            }
+        }
+        cells
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::MemoryBackend;
+    use crate::config::Cli;
+    use crate::residue::Decay;
+    use crate::rng::new_rng;
+    use clap::Parser;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Runtime {
+        controls: Controls,
+        frame: FrameBuffer,
+        residue: Residue,
+        faller_adder: FallerAdder,
+        timestep: Timestep,
+    }
+
+    impl Runtime {
+        fn new() -> Self {
+            let size = Position::new(60, 20);
+            let mut config = Config::default();
+            config.set_screen_size(size);
+            Self {
+                controls: Controls::new(&config).unwrap(),
+                frame: FrameBuffer::new(size),
+                residue: Residue::new(size, Decay::default(), "", 30),
+                faller_adder: FallerAdder::new(Rc::new(RefCell::new(config)), new_rng(Some(1))),
+                timestep: Timestep::new(Duration::from_millis(10), 1.0),
+            }
+        }
+
+        fn apply(&mut self, action: Action) {
+            self.controls.apply(
+                action,
+                &mut self.frame,
+                &mut self.residue,
+                &mut self.faller_adder,
+                &mut self.timestep,
+            );
+        }
+    }
+
+    #[test]
+    fn parse_key_names() {
+        assert_eq!(parse_key("q").unwrap(), Key::Char('q'));
+        assert_eq!(parse_key("Q").unwrap(), Key::Char('Q'));
+        assert_eq!(parse_key("+").unwrap(), Key::Char('+'));
+        assert_eq!(parse_key("Ctrl+X").unwrap(), Key::Ctrl('x'));
+        assert_eq!(parse_key("alt+X").unwrap(), Key::Alt('X'));
+        assert_eq!(parse_key("ctrl++").unwrap(), Key::Ctrl('+'));
+        assert_eq!(parse_key("shift+tab").unwrap(), Key::BackTab);
+        assert_eq!(parse_key("F12").unwrap(), Key::F(12));
+        assert_eq!(parse_key("PageUp").unwrap(), Key::PageUp);
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("hyper+x").is_err());
+        assert!(parse_key("qq").is_err());
+        for (name, _) in DEFAULT_KEY_BINDINGS {
+            assert_eq!(key_name(&parse_key(name).unwrap()), name);
+        }
+    }
+
+    #[test]
+    fn configured_bindings_override_defaults() {
+        let defaults = KeyBindings::new(&BTreeMap::new()).unwrap();
+        assert_eq!(defaults.action(&Key::Ctrl('c')), Some(Action::Quit));
+        assert_eq!(defaults.action(&Key::Up), Some(Action::Faster));
+        assert_eq!(defaults.action(&Key::Char('x')), None);
+
+        let configured = BTreeMap::from([
+            ("q".to_owned(), None),
+            ("ctrl+c".to_owned(), None),
+            ("alt+q".to_owned(), Some(Action::Quit)),
+            ("space".to_owned(), Some(Action::Help)),
+        ]);
+        let bindings = KeyBindings::new(&configured).unwrap();
+        assert_eq!(bindings.action(&Key::Char('q')), None);
+        assert_eq!(bindings.action(&Key::Ctrl('c')), None);
+        assert_eq!(bindings.keys_for(Action::Quit), ["alt+q"]);
+        assert_eq!(bindings.keys_for(Action::Help), ["?", "space"]);
+        assert!(bindings.keys_for(Action::Pause).is_empty());
+
+        let wrong = BTreeMap::from([("hyper+q".to_owned(), Some(Action::Quit))]);
+        assert!(KeyBindings::new(&wrong).is_err());
+    }
+
+    #[test]
+    fn pause_speed_and_fallers() {
+        let mut runtime = Runtime::new();
+        runtime.apply(Action::Pause);
+        assert!(runtime.timestep.is_paused());
+        runtime.apply(Action::Step);
+        assert_eq!(runtime.timestep.tick(), 1);
+        runtime.apply(Action::Pause);
+        assert!(!runtime.timestep.is_paused());
+
+        runtime.apply(Action::Faster);
+        assert_eq!(runtime.timestep.time_scale(), 1.25);
+        runtime.apply(Action::Slower);
+        runtime.apply(Action::Slower);
+        assert_eq!(runtime.timestep.time_scale(), 0.8);
+
+        runtime.apply(Action::MoreFallers);
+        assert_eq!(*runtime.faller_adder.config.borrow().no_fallers(), 55);
+        for _ in 0..100 {
+            runtime.apply(Action::FewerFallers);
+        }
+        assert_eq!(*runtime.faller_adder.config.borrow().no_fallers(), 1);
+    }
+
+    #[test]
+    fn theme_and_message() {
+        let mut runtime = Runtime::new();
+        runtime.apply(Action::NextTheme);
+        let amber = Theme::builtin("amber").unwrap();
+        assert_eq!(runtime.faller_adder.config.borrow().colors(), &amber.colors);
+
+        assert!(runtime.faller_adder.config.borrow().message().is_some());
+        runtime.apply(Action::ToggleMessage);
+        assert!(runtime.faller_adder.config.borrow().message().is_none());
+        runtime.apply(Action::ToggleMessage);
+        let config = runtime.faller_adder.config.borrow();
+        assert_eq!(config.message().as_ref().unwrap().color, amber.message_color.unwrap());
+    }
+
+    #[test]
+    fn reload_keeps_theme_and_hidden_message() {
+        let mut runtime = Runtime::new();
+        runtime.apply(Action::NextTheme);
+        runtime.apply(Action::ToggleMessage);
+        let mut config = Config::default();
+        runtime.controls.reload(&mut config).unwrap();
+        runtime.faller_adder.reload(config);
+        assert_eq!(runtime.controls.theme, 1);
+        let amber = Theme::builtin("amber").unwrap();
+        assert_eq!(runtime.faller_adder.config.borrow().colors(), &amber.colors);
+        assert_eq!(runtime.faller_adder.config.borrow().chars_to_use(), &amber.chars_to_use.unwrap());
+        assert!(runtime.faller_adder.config.borrow().message().is_none());
+
+        runtime.apply(Action::ToggleMessage);
+        assert!(runtime.faller_adder.config.borrow().message().is_some());
+        runtime.apply(Action::NextTheme);
+        assert_eq!(runtime.controls.theme, 2);
+    }
+
+    #[test]
+    fn reload_uses_theme_changed_in_file() {
+        let mut runtime = Runtime::new();
+        runtime.apply(Action::NextTheme);
+        let mut config: Config = serde_yaml::from_str("theme: ice").unwrap();
+        config.apply_cli(&Cli::parse_from(["rumatrix"])).unwrap();
+        runtime.controls.reload(&mut config).unwrap();
+        runtime.faller_adder.reload(config);
+        assert_eq!(BUILTIN_THEMES[runtime.controls.theme], "ice");
+        let ice = Theme::builtin("ice").unwrap();
+        assert_eq!(runtime.faller_adder.config.borrow().colors(), &ice.colors);
+
+        // file without theme goes back to the default one
+        let mut config = Config::default();
+        runtime.controls.reload(&mut config).unwrap();
+        assert_eq!(runtime.controls.theme, 0);
+    }
+
+    #[test]
+    fn help_is_drawn_over_frame() {
+        let mut runtime = Runtime::new();
+        let size = runtime.frame.size();
+        let mut backend = MemoryBackend::new(size);
+        assert!(runtime
+            .controls
+            .overlay(&runtime.faller_adder, &runtime.timestep, size)
+            .is_empty());
+        runtime.apply(Action::Help);
+        let overlay = runtime
+            .controls
+            .overlay(&runtime.faller_adder, &runtime.timestep, size);
+        runtime.frame.flush_with_overlay(&overlay, &mut backend).unwrap();
+        let text = backend.text();
+        assert!(text.contains("ruMatrix keys"));
+        assert!(text.contains("space  pause / resume"));
+        assert!(text.contains("q, ctrl+c  quit"));
+        assert!(text.contains("speed x1.00, 50 fallers, classic theme"));
+
+        runtime.apply(Action::Help);
+        runtime.frame.flush_with_overlay(&[], &mut backend).unwrap();
+        assert!(backend.text().trim().is_empty());
+    }
+
+    #[test]
+    fn notice_is_drawn_in_last_row() {
+        let mut runtime = Runtime::new();
+        let size = Position::new(20, 3);
+        runtime.controls.show_notice("config not reloaded: bad", true);
+        let overlay = runtime.controls.overlay(&runtime.faller_adder, &runtime.timestep, size);
+        assert_eq!(overlay.len(), 20);
+        assert!(overlay.iter().all(|(position, _)| position.y() == 3));
+        assert_eq!(overlay[1].1, Cell::new('c', Color::Palette(15)).with_background(Some(Color::Palette(1))));
+
+        let mut config = runtime.faller_adder.config.borrow().clone();
+        runtime.controls.reload(&mut config).unwrap();
+        let overlay = runtime.controls.overlay(&runtime.faller_adder, &runtime.timestep, size);
+        assert_eq!(overlay.len(), 20);
+    }
+
+    #[test]
+    fn help_aligns_wide_key_names() {
+        let mut runtime = Runtime::new();
+        let keys = BTreeMap::from([("é".to_owned(), Some(Action::Quit))]);
+        runtime.controls.bindings = KeyBindings::new(&keys).unwrap();
+        runtime.apply(Action::Help);
+        let size = runtime.frame.size();
+        let overlay = runtime.controls.overlay(&runtime.faller_adder, &runtime.timestep, size);
+        let column_of = |description: &str| {
+            let description = unicode::graphemes(description);
+            overlay
+                .windows(description.len())
+                .find(|cells| cells.iter().map(|(_, cell)| cell.symbol.as_str()).eq(description.iter().copied()))
+                .map(|cells| cells[0].0.x())
+        };
+        assert!(column_of("quit").is_some());
+        assert_eq!(column_of("quit"), column_of("pause / resume"));
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt::{self, Display};
+use std::io;
+
+/// Errors which can stop ruMatrix
+#[derive(Debug)]
+pub enum Error {
+    /// Config file cannot be read or parsed, or config is otherwise incorrect
+    ConfigParse(String),
+    /// Color cannot be parsed from provided value
+    InvalidColor(String),
+    /// Message does not fit on the screen
+    MessageTooLong {
+        /// Length of the message in terminal columns, wide characters take two
+        length: usize,
+        /// Width of the screen
+        width: u16,
+    },
+    /// Program is not running in a terminal
+    NoTerminal,
+    /// Command sent to [crate::control_socket::ControlSocket] is incorrect or cannot be executed
+    ControlSocket(String),
+    /// Any other IO error
+    Io(io::Error),
+}
+
+impl Error {
+    /// Exit code of the process which should be used when program stops because of the error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::ConfigParse(_) => 2,
+            Error::InvalidColor(_) => 3,
+            Error::MessageTooLong { .. } => 4,
+            Error::NoTerminal => 5,
+            Error::Io(_) => 6,
+            Error::ControlSocket(_) => 7,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ConfigParse(reason) => write!(f, "incorrect configuration: {reason}"),
+            Error::InvalidColor(reason) => write!(f, "incorrect color: {reason}"),
+            Error::MessageTooLong { length, width } => write!(
+                f,
+                "message is {length} characters long, but screen is only {width} characters wide"
+            ),
+            Error::NoTerminal => write!(
+                f,
+                "not running in a terminal, ruMatrix needs terminal to display its output"
+            ),
+            Error::Io(error) => write!(f, "{error}"),
+            Error::ControlSocket(reason) => write!(f, "incorrect command: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let errors = [
+            Error::ConfigParse("".into()),
+            Error::InvalidColor("".into()),
+            Error::MessageTooLong {
+                length: 1,
+                width: 0,
+            },
+            Error::NoTerminal,
+            Error::Io(io::Error::other("")),
+            Error::ControlSocket("".into()),
+        ];
+        let mut codes: Vec<_> = errors.iter().map(Error::exit_code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(codes.iter().all(|&code| code > 1));
+    }
+
+    #[test]
+    fn message_too_long_display() {
+        let error = Error::MessageTooLong {
+            length: 12,
+            width: 10,
+        };
+        assert_eq!(
+            error.to_string(),
+            "message is 12 characters long, but screen is only 10 characters wide"
+        );
+    }
+}
@@ -1,7 +1,16 @@
 use std::{cell::RefCell, rc::Rc};
 
 use crate::{
-    config::Config, falling_char::FallingChar, position::Position, random_vec_bag::RandomVecBag,
+    colors::Color,
+    config::Config,
+    error::Error,
+    falling_char::FallingChar,
+    message::Message,
+    position::{Position, PositionTrait},
+    random_vec_bag::RandomVecBag,
+    rng::SharedRng,
+    theme::Theme,
+    unicode,
 };
 use rand::prelude::*;
 
@@ -9,32 +18,165 @@ use rand::prelude::*;
 #[derive(Debug)]
 pub struct ProbabilityOutOfBoundsError;
 
+impl From<ProbabilityOutOfBoundsError> for Error {
+    fn from(_: ProbabilityOutOfBoundsError) -> Self {
+        Error::ConfigParse("probability to add fallers needs to be between 0.0 and 1.0".into())
+    }
+}
+
 /// Structure holds abstract being which can add new [FallingChar]s or delete existing
-pub struct FallerAdder<'a> {
-    /// [ThreadRng] to use
+pub struct FallerAdder {
+    /// [RngCore] to use, see [crate::rng::new_rng]
     pub rng: Rc<RefCell<dyn RngCore>>,
     /// [Vec<FallingChar>] holding all instances of [FallingChar]
-    pub falling_chars: Rc<RefCell<Vec<FallingChar<'a>>>>,
+    pub falling_chars: Rc<RefCell<Vec<FallingChar>>>,
     /// Probability to add new falling char on the screen. Value should satisfy `0.0 <= probability <= 1.0` otherwise [ProbabilityOutOfBoundsError] will be generated.
     pub probability_to_add: f64,
     /// Possible x positions of new [FallingChar]s
-    pub positions: &'a mut RandomVecBag<u16>,
+    pub positions: RandomVecBag<u16>,
     /// Configuration to be used when adding new [FallingChar]
-    pub config: &'a Config,
+    pub config: Rc<RefCell<Config>>,
+    /// Number of frames since start, used by [crate::colors::ColorMode::HueCycle]
+    pub frame: u64,
 }
 
-impl<'a> FallerAdder<'a> {
+/// Default probability to add new [FallingChar] in each frame
+const DEFAULT_PROBABILITY_TO_ADD: f64 = 0.22;
+
+impl FallerAdder {
+    /// Create new [FallerAdder] without any [FallingChar]s, using `config` and drawing random numbers from `rng`
+    pub fn new(config: Rc<RefCell<Config>>, rng: Rc<RefCell<dyn RngCore>>) -> Self {
+        let no_fallers = *config.borrow().no_fallers();
+        let width = config.borrow().screen_size().x();
+        let column_width = Self::column_width(config.borrow().chars_to_use());
+        Self {
+            falling_chars: Rc::new(RefCell::new(Vec::with_capacity(no_fallers))),
+            probability_to_add: DEFAULT_PROBABILITY_TO_ADD,
+            positions: Self::position_bag(width, column_width, Rc::clone(&rng)),
+            rng,
+            config,
+            frame: 0,
+        }
+    }
+
+    /// Width of columns in which [FallingChar]s fall: 2 if `chars_to_use` contain wide characters, 1 otherwise
+    pub fn column_width(chars_to_use: &str) -> u16 {
+        let wide = unicode::graphemes(chars_to_use)
+            .into_iter()
+            .any(|grapheme| unicode::grapheme_width(grapheme) > 1);
+        if wide {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Create [RandomVecBag] of possible x positions of [FallingChar]s for screen of `width`, shuffled using `rng`
+    ///
+    /// Positions are `column_width` apart, so that wide characters of neighbouring fallers do not overlap
+    pub fn position_bag(width: u16, column_width: u16, rng: Rc<RefCell<dyn RngCore>>) -> RandomVecBag<u16> {
+        let column_width = column_width.max(1);
+        // wide character in the last column would not fit
+        let last = (width + 1).saturating_sub(column_width).max(1);
+        let mut vec: Vec<u16> = Vec::with_capacity(usize::from(width) * 2);
+        // we want unique positions for fallers, but it still looks cool if some fallers fall at the same time at the same position
+        for _ in 1..=2 {
+            vec.extend((1..=last).step_by(usize::from(column_width)));
+        }
+        RandomVecBag::with_custom_rng(vec, Box::new(SharedRng(rng)))
+    }
+
+    /// Re-layout after screen was resized to `size`
+    ///
+    /// Rebuilds possible positions and updates bounds of existing [FallingChar]s,
+    /// ones which are outside of new screen are retired.
+    pub fn resize(&mut self, size: Position) {
+        if size.x() != self.config.borrow().screen_size().x() {
+            let column_width = Self::column_width(self.config.borrow().chars_to_use());
+            self.positions = Self::position_bag(size.x(), column_width, Rc::clone(&self.rng));
+        }
+        self.config.borrow_mut().set_screen_size(size);
+        let mut falling_chars = self.falling_chars.borrow_mut();
+        for f in falling_chars.iter_mut() {
+            f.set_max_position(size);
+        }
+        falling_chars.retain(|f| f.should_be_retained());
+    }
+
+    /// Use colors, message color and chars to use from `theme` for new [FallingChar]s, existing ones change
+    /// only color of the message
+    pub fn apply_theme(&mut self, theme: &Theme) {
+        let mut config = self.config.borrow_mut();
+        let column_width = Self::column_width(config.chars_to_use());
+        config.apply_theme(theme);
+        if Self::column_width(config.chars_to_use()) != column_width {
+            let column_width = Self::column_width(config.chars_to_use());
+            self.positions = Self::position_bag(config.screen_size().x(), column_width, Rc::clone(&self.rng));
+        }
+        for f in self.falling_chars.borrow_mut().iter_mut() {
+            f.set_message(config.message().clone());
+        }
+    }
+
+    /// Show `message` on the screen, or hide it if [None]
+    pub fn set_message(&mut self, message: Option<Message>) {
+        let mut config = self.config.borrow_mut();
+        config.set_message(message);
+        for f in self.falling_chars.borrow_mut().iter_mut() {
+            f.set_message(config.message().clone());
+        }
+    }
+
+    /// Use `config` reloaded from file, screen size stays the same
+    ///
+    /// Existing [FallingChar]s get new colors and message, everything else is used by new ones.
+    pub fn reload(&mut self, mut config: Config) {
+        let size = *self.config.borrow().screen_size();
+        config.set_screen_size(size);
+        let column_width = Self::column_width(config.chars_to_use());
+        if column_width != Self::column_width(self.config.borrow().chars_to_use()) {
+            self.positions = Self::position_bag(size.x(), column_width, Rc::clone(&self.rng));
+        }
+        self.set_colors(&config);
+        for f in self.falling_chars.borrow_mut().iter_mut() {
+            f.set_message(config.message().clone());
+        }
+        *self.config.borrow_mut() = config;
+    }
+
+    /// Use `trail` color for all [FallingChar]s, including existing ones, see [Config::set_trail_color]
+    pub fn set_trail_color(&mut self, trail: Color) {
+        self.config.borrow_mut().set_trail_color(trail);
+        self.set_colors(&self.config.borrow());
+    }
+
+    /// Give existing [FallingChar]s colors picked by color mode of `config`
+    fn set_colors(&self, config: &Config) {
+        for f in self.falling_chars.borrow_mut().iter_mut() {
+            let colors = config.color_mode().colors_for(
+                config.colors(),
+                config.auto_colors(),
+                &mut *self.rng.borrow_mut(),
+                f.column(),
+                config.screen_size().x(),
+                self.frame,
+            );
+            f.set_colors(colors);
+        }
+    }
+
     /// Adds new [FallingChar]s and retires old ones (e.g. because they are not visible on the screen)
     pub fn add_and_retire(&mut self) -> Result<(), ProbabilityOutOfBoundsError> {
         if !(0.0..=1.0).contains(&self.probability_to_add) {
             return Err(ProbabilityOutOfBoundsError);
         }
 
+        let config = self.config.borrow();
         let mut falling_chars = self.falling_chars.borrow_mut();
         // retire old fallers
         falling_chars.retain(|f| f.should_be_retained());
 
-        for _ in falling_chars.len()..*self.config.no_fallers() {
+        for _ in falling_chars.len()..*config.no_fallers() {
             if self.rng.borrow_mut().gen_bool(self.probability_to_add) {
                 let position = Position::new(
                     *self
@@ -43,16 +185,30 @@ impl<'a> FallerAdder<'a> {
                         .expect("Cannot get random position from bag"),
                     1,
                 );
-                falling_chars.push(FallingChar::new(
-                    Rc::clone(&self.rng),
-                    position,
-                    *self.config.screen_size(),
-                    self.config.colors(),
-                    self.config.chars_to_use(),
-                    self.config.message().clone(),
-                ))
+                let colors = config.color_mode().colors_for(
+                    config.colors(),
+                    config.auto_colors(),
+                    &mut *self.rng.borrow_mut(),
+                    position.x(),
+                    config.screen_size().x(),
+                    self.frame,
+                );
+                let speed = config.speed().pick(&mut *self.rng.borrow_mut());
+                let velocity = speed / *config.fps() as f32;
+                falling_chars.push(
+                    FallingChar::new(
+                        Rc::clone(&self.rng),
+                        position,
+                        *config.screen_size(),
+                        colors,
+                        config.chars_to_use(),
+                        config.message().clone(),
+                    )
+                    .with_velocity(velocity),
+                )
             }
         }
+        self.frame += 1;
         Ok(())
     }
 }
@@ -1,37 +1,87 @@
-use crate::{colors::Colors, message::Message, position::*};
-use rand::prelude::*;
-use std::{
-    cmp::max,
-    io::{Stdout, Write}, cell::RefCell, rc::Rc,
+use crate::{
+    colors::Colors,
+    error::Error,
+    frame_buffer::{Cell, FrameBuffer},
+    message::Message,
+    position::*,
+    unicode,
 };
-use termion::raw::RawTerminal;
-use termion::{cursor, screen::AlternateScreen, style};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, cmp::max, rc::Rc};
+
+/// Range of speeds of [FallingChar]s in cells per second, each one gets random speed from it
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(default)]
+pub struct Speed {
+    /// Lowest speed
+    pub min: f32,
+    /// Highest speed
+    pub max: f32,
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Self { min: 12.0, max: 30.0 }
+    }
+}
+
+impl Speed {
+    /// Parse [Speed] from `speed` in `min-max` or `speed` (for all fallers) syntax, e.g. `8.5-30`
+    pub fn parse(speed: &str) -> Result<Self, Error> {
+        let invalid = || {
+            Error::ConfigParse(format!(
+                "'{speed}' is not a speed range, use min-max cells per second e.g.: 8.5-30"
+            ))
+        };
+        let (min, max) = speed.split_once('-').unwrap_or((speed, speed));
+        let min: f32 = min.trim().parse().map_err(|_| invalid())?;
+        let max: f32 = max.trim().parse().map_err(|_| invalid())?;
+        if !(min > 0.0 && min <= max && max.is_finite()) {
+            return Err(invalid());
+        }
+        Ok(Self { min, max })
+    }
+
+    /// Pick random speed from the range
+    pub fn pick(&self, rng: &mut dyn RngCore) -> f32 {
+        if self.min < self.max {
+            rng.gen_range(self.min..=self.max)
+        } else {
+            self.min
+        }
+    }
+}
 
 /// Structure to hold `FallingChar` currently to be displayed on the screen
-pub struct FallingChar<'a> {
+pub struct FallingChar {
     /// Current position on the screen
     position: Position,
     /// Vector of previous positions to display trail
     previous_positions: Vec<Position>,
     /// Upper bounds for position
     max_position: Position,
-    /// Chars which will be used to render both current position of `FallingChar` and its trail
-    chars_to_render: Vec<char>,
+    /// Graphemes which will be used to render both current position of `FallingChar` and its trail
+    chars_to_render: Vec<String>,
     /// Colors to be used in char display
-    colors: &'a Colors,
+    colors: Colors,
     /// Size of the trail
     size: u16,
     /// Optional message to be displayed on the screen
     message: Option<Message>,
+    /// Rows travelled in each frame, at most 1.0
+    velocity: f32,
+    /// Part of the row travelled since last move
+    progress: f32,
 }
 
-impl<'a> FallingChar<'a> {
+impl FallingChar {
     /// Create new instance of [FallingChar]
     pub fn new(
         rng: Rc<RefCell<dyn RngCore>>,
         position: Position,
         max_position: Position,
-        colors: &'a Colors,
+        colors: Colors,
         chars_to_use: &str,
         message: Option<Message>,
     ) -> Self {
@@ -44,94 +94,287 @@ impl<'a> FallingChar<'a> {
             colors,
             size,
             message,
+            velocity: 1.0,
+            progress: 0.0,
         }
     }
 
-    /// Get randomly ordered chars to be used in rendering process
-    fn get_random_chars(rng: Rc<RefCell<dyn RngCore>>, size: u16, chars_to_use: &str) -> Vec<char> {
+    /// Same [FallingChar] travelling `velocity` rows in each frame
+    ///
+    /// Velocity is limited to one row per frame, so that no row of the trail is skipped
+    pub fn with_velocity(mut self, velocity: f32) -> Self {
+        self.velocity = velocity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Get randomly ordered graphemes of `chars_to_use` to be used in rendering process
+    fn get_random_chars(rng: Rc<RefCell<dyn RngCore>>, size: u16, chars_to_use: &str) -> Vec<String> {
         // these two lines do not work when put in one line for some reason
         let rng = Rc::clone(&rng);
         let mut rng = rng.borrow_mut();
-        let chars_to_select: Vec<_> = chars_to_use.chars().collect();
+        let chars_to_select = unicode::graphemes(chars_to_use);
 
-        let mut random_chars: Vec<char> = Vec::with_capacity(size as usize);
+        let mut random_chars: Vec<String> = Vec::with_capacity(size as usize);
         while random_chars.len() < size as usize {
             let index = rng.gen_range(0..chars_to_select.len());
             let selected_char = chars_to_select[index];
-            random_chars.push(selected_char);
+            random_chars.push(selected_char.to_owned());
         }
         random_chars
     }
 
     /// Should this instance of [FallingChar] be retained or cleaned by [FallerAdder]
     pub fn should_be_retained(&self) -> bool {
-        !self
-            .previous_positions
-            .iter()
-            .all(|&pp| pp.is_out_of_bounds(&self.max_position))
+        // slow faller might not have any trail yet
+        !self.position.is_out_of_bounds(&self.max_position)
+            || !self
+                .previous_positions
+                .iter()
+                .all(|&pp| pp.is_out_of_bounds(&self.max_position))
     }
 
-    /// Render character and its trail on the `screen`
-    pub fn render(&self, rng: &mut ThreadRng, screen: &mut AlternateScreen<RawTerminal<Stdout>>) {
+    /// Render character and its trail into the `frame`
+    pub fn render(&self, rng: &mut dyn RngCore, frame: &mut FrameBuffer) {
         if !self.position.is_out_of_bounds(&self.max_position) {
-            let char_to_render: char = self.chars_to_render[0];
-            write!(
-                screen,
-                "{}{}{}{}{}",
-                cursor::Goto(self.position.x(), self.position.y()),
-                style::Bold,
-                self.colors.head.get_ansi_string(),
-                char_to_render,
-                style::Reset
-            )
-            .unwrap();
+            let char_to_render = self.chars_to_render[0].as_str();
+            frame.put(
+                &self.position,
+                Cell::new(char_to_render, self.colors.head.clone()).bold(),
+            );
         }
 
         if !self.previous_positions.is_empty() {
             for (i, pos) in self.previous_positions.iter().enumerate() {
                 if !pos.is_out_of_bounds(&self.max_position) {
-                    let mut char_to_render = self.chars_to_render[i];
-                    let mut color_to_use = self.colors.trail.get_ansi_string();
-                    if i == self.size as usize - 1 {
-                        color_to_use = self.colors.left_behind.get_ansi_string()
+                    let mut char_to_render = self.chars_to_render[i].as_str();
+                    let mut position = *pos;
+                    let message_char;
+                    let gradient_color;
+                    let mut background = None;
+                    // gradient ends with its own last stop, plain trail ends with left behind color
+                    let mut color_to_use = match self.colors.gradient {
+                        Some(ref gradient) => {
+                            let t = i as f32 / max(1, self.size - 1) as f32;
+                            gradient_color = gradient.color_at(t);
+                            &gradient_color
+                        }
+                        None if i == self.size as usize - 1 => &self.colors.left_behind,
+                        None => &self.colors.trail,
                     };
                     if i == self.previous_positions.len() - 1 {
-                        char_to_render = self.chars_to_render.choose(rng).unwrap().to_owned();
+                        char_to_render = self.chars_to_render.choose(rng).unwrap();
                         if let Some(message) = &self.message {
-                            char_to_render =
-                                message.get_char_in_position(pos).unwrap_or(char_to_render);
-                            if message.is_position_inside_message(pos) {
-                                color_to_use = message.color.get_ansi_string();
+                            // wide grapheme of the message is drawn also when its second column is passed
+                            let mut message_grapheme = message.get_grapheme_in_position(pos);
+                            if message_grapheme.is_none() && unicode::grapheme_width(char_to_render) > 1 {
+                                // wide character would cover the first column of the message
+                                let next = Position::new(pos.x() + 1, pos.y());
+                                message_grapheme = message.get_grapheme_in_position(&next);
+                            }
+                            if let Some((start, grapheme)) = message_grapheme {
+                                message_char = grapheme;
+                                char_to_render = &message_char;
+                                position = start;
+                                color_to_use = &message.color;
+                                background = message.background.clone();
                             }
                         }
                     }
 
-                    write!(
-                        screen,
-                        "{}{}{}{}",
-                        cursor::Goto(pos.x(), pos.y()),
-                        color_to_use,
-                        char_to_render,
-                        style::Reset
-                    )
-                    .unwrap();
+                    frame.put(
+                        &position,
+                        Cell::new(char_to_render, color_to_use.clone()).with_background(background),
+                    );
                 }
             }
         }
     }
 
+    /// Change upper bounds for position, e.g. after terminal was resized
+    ///
+    /// [FallingChar] which ends up completely outside of new bounds will not be retained
+    pub fn set_max_position(&mut self, max_position: Position) {
+        self.max_position = max_position;
+        if let Some(ref mut message) = self.message {
+            message.bounds = max_position;
+            // message which does not fit stays where it was, it is just partially hidden
+            let _ = message.update_position();
+        }
+    }
+
+    /// Column in which this [FallingChar] falls
+    pub fn column(&self) -> u16 {
+        self.position.x()
+    }
+
+    /// [Colors] of character and its trail
+    pub fn colors(&self) -> &Colors {
+        &self.colors
+    }
+
+    /// Change [Colors] of character and its trail, e.g. after config was reloaded
+    pub fn set_colors(&mut self, colors: Colors) {
+        self.colors = colors;
+    }
+
+    /// Change message displayed by this [FallingChar], e.g. after it was hidden or its color changed
+    pub fn set_message(&mut self, message: Option<Message>) {
+        self.message = message;
+    }
+
     /// Advance char position
-    pub fn advance(&mut self) {
+    ///
+    /// Returns [Position] of character left behind by the end of the trail, if it is not part of the message
+    pub fn advance(&mut self) -> Option<Position> {
+        // slower fallers move only in some frames
+        self.progress += self.velocity;
+        if self.progress < 1.0 {
+            return None;
+        }
+        self.progress -= 1.0;
+        let mut left_behind = None;
         if self.previous_positions.len() >= self.size.into() {
-            let size = self.previous_positions.len();
-            if size > 0 {
-                self.previous_positions.remove(size - 1);
-            }
+            left_behind = self.previous_positions.pop();
         }
         self.previous_positions.insert(0, self.position);
         self.position.set_y(self.position.y() + 1);
         if let Some(ref mut message) = self.message {
-            message.update_position()
+            // text might have changed (e.g. current time) so that it does not fit, keep previous position then
+            let _ = message.update_position();
+        }
+        match self.message {
+            Some(ref message) => left_behind.filter(|pos| !message.is_position_inside_message(pos)),
+            None => left_behind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::colors::Color;
+    use crate::gradient::{Easing, Gradient};
+    use rand::rngs::mock::StepRng;
+
+    fn test_colors() -> Colors {
+        Colors {
+            trail: Color::Palette(2),
+            head: Color::Palette(10),
+            left_behind: Color::Palette(2),
+            gradient: None,
+            background: None,
+        }
+    }
+
+    fn test_faller(x: u16, max_position: Position) -> FallingChar {
+        FallingChar::new(
+            Rc::new(RefCell::new(StepRng::new(0, 1))),
+            Position::new(x, 1),
+            max_position,
+            test_colors(),
+            "abc",
+            None,
+        )
+    }
+
+    #[test]
+    fn retained_after_resize_only_if_visible() {
+        let mut inside = test_faller(5, Position::new(10, 10));
+        let mut outside = test_faller(8, Position::new(10, 10));
+        inside.advance();
+        outside.advance();
+        assert!(inside.should_be_retained());
+        assert!(outside.should_be_retained());
+
+        inside.set_max_position(Position::new(6, 4));
+        outside.set_max_position(Position::new(6, 4));
+        assert!(inside.should_be_retained());
+        assert!(!outside.should_be_retained());
+    }
+
+    #[test]
+    fn advance_returns_left_behind_position() {
+        // StepRng always gives the lowest value, so trail has size of one third of the screen
+        let mut faller = FallingChar::new(
+            Rc::new(RefCell::new(StepRng::new(0, 0))),
+            Position::new(1, 1),
+            Position::new(1, 6),
+            test_colors(),
+            "a",
+            None,
+        );
+        assert_eq!(faller.advance(), None);
+        assert_eq!(faller.advance(), None);
+        assert_eq!(faller.advance(), Some(Position::new(1, 1)));
+        assert_eq!(faller.advance(), Some(Position::new(1, 2)));
+    }
+
+    #[test]
+    fn slow_faller_moves_in_some_frames() {
+        let mut faller = test_faller(1, Position::new(1, 10)).with_velocity(0.4);
+        let rows: Vec<u16> = (0..5)
+            .map(|_| {
+                faller.advance();
+                faller.position.y()
+            })
+            .collect();
+        assert_eq!(rows, [1, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn parse_speed() {
+        assert_eq!(Speed::parse("8.5-30").unwrap(), Speed { min: 8.5, max: 30.0 });
+        assert_eq!(Speed::parse("12").unwrap(), Speed { min: 12.0, max: 12.0 });
+        assert!(Speed::parse("30-8").is_err());
+        assert!(Speed::parse("0").is_err());
+        assert!(Speed::parse("fast").is_err());
+    }
+
+    #[test]
+    fn trail_uses_gradient() {
+        let mut colors = test_colors();
+        colors.gradient = Some(Gradient::parse("0:0,0,0;100:100,100,100", Easing::Linear).unwrap());
+        // StepRng always gives the lowest value, so trail has size of one third of the screen
+        let mut faller = FallingChar::new(
+            Rc::new(RefCell::new(StepRng::new(0, 0))),
+            Position::new(1, 1),
+            Position::new(1, 15),
+            colors,
+            "a",
+            None,
+        );
+        for _ in 0..6 {
+            faller.advance();
+        }
+        let mut frame = FrameBuffer::new(Position::new(1, 15));
+        faller.render(&mut StepRng::new(0, 0), &mut frame);
+        let color = |y| frame.get(&Position::new(1, y)).unwrap().style.color.clone();
+        assert_eq!(color(6), Some(Color::RGB { r: 0, g: 0, b: 0 }));
+        assert_eq!(color(4), Some(Color::RGB { r: 50, g: 50, b: 50 }));
+        assert_eq!(color(3), Some(Color::RGB { r: 75, g: 75, b: 75 }));
+        // end of the trail has the last color of the gradient
+        assert_eq!(color(2), Some(Color::RGB { r: 100, g: 100, b: 100 }));
+    }
+
+    #[test]
+    fn trail_without_gradient_ends_with_left_behind() {
+        let mut colors = test_colors();
+        colors.left_behind = Color::Palette(8);
+        let mut faller = FallingChar::new(
+            Rc::new(RefCell::new(StepRng::new(0, 0))),
+            Position::new(1, 1),
+            Position::new(1, 15),
+            colors,
+            "a",
+            None,
+        );
+        for _ in 0..6 {
+            faller.advance();
         }
+        let mut frame = FrameBuffer::new(Position::new(1, 15));
+        faller.render(&mut StepRng::new(0, 0), &mut frame);
+        let color = |y| frame.get(&Position::new(1, y)).unwrap().style.color.clone();
+        assert_eq!(color(3), Some(Color::Palette(2)));
+        assert_eq!(color(2), Some(Color::Palette(8)));
     }
 }
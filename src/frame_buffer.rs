@@ -0,0 +1,424 @@
+use crate::backend::Backend;
+use crate::colors::{Color, ColorDepth};
+use crate::position::{Position, PositionTrait};
+use crate::unicode;
+use std::io;
+use termion::style;
+
+/// Style (colors and attributes) of a [Cell]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Style {
+    /// Foreground [Color], [None] means terminal default
+    pub color: Option<Color>,
+    /// Should cell be displayed in bold
+    pub bold: bool,
+    /// Background [Color], [None] means background of the [FrameBuffer]
+    pub background: Option<Color>,
+}
+
+impl Style {
+    /// Get ANSI string for [Style], it resets previous style first
+    pub fn get_ansi_string(&self) -> String {
+        let mut ansi = style::Reset.to_string();
+        if self.bold {
+            ansi.push_str(style::Bold.as_ref());
+        }
+        if let Some(ref color) = self.color {
+            ansi.push_str(&color.get_ansi_string());
+        }
+        if let Some(ref background) = self.background {
+            ansi.push_str(&background.get_ansi_bg_string());
+        }
+        ansi
+    }
+
+    /// Same [Style] with colors which can be displayed by terminal with `depth`, see [Color::downgrade]
+    pub fn downgrade(&self, depth: ColorDepth) -> Style {
+        Style {
+            color: self.color.as_ref().map(|color| color.downgrade(depth)),
+            background: self.background.as_ref().map(|color| color.downgrade(depth)),
+            ..self.clone()
+        }
+    }
+}
+
+/// Single cell of the [FrameBuffer]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Cell {
+    /// Grapheme displayed in the cell, empty if the cell is covered by wide grapheme from the previous one
+    pub symbol: String,
+    /// [Style] of the cell
+    pub style: Style,
+}
+
+impl Cell {
+    /// Create new [Cell] with `symbol` (single grapheme) displayed using `color`
+    pub fn new(symbol: impl Into<String>, color: Color) -> Self {
+        Self {
+            symbol: symbol.into(),
+            style: Style {
+                color: Some(color),
+                bold: false,
+                background: None,
+            },
+        }
+    }
+
+    /// Same [Cell] but displayed in bold
+    pub fn bold(mut self) -> Self {
+        self.style.bold = true;
+        self
+    }
+
+    /// Same [Cell] but displayed on `background`
+    pub fn with_background(mut self, background: Option<Color>) -> Self {
+        self.style.background = background;
+        self
+    }
+
+    /// Empty [Cell] displayed on `background`
+    pub fn blank(background: Option<Color>) -> Self {
+        Self::default().with_background(background)
+    }
+
+    /// Does the symbol take two columns, see [unicode::grapheme_width]
+    pub fn is_wide(&self) -> bool {
+        !self.symbol.is_empty() && unicode::grapheme_width(&self.symbol) > 1
+    }
+
+    /// Is this cell covered by wide symbol from the previous one
+    pub fn is_continuation(&self) -> bool {
+        self.symbol.is_empty()
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            symbol: " ".into(),
+            style: Style::default(),
+        }
+    }
+}
+
+/// Double-buffered grid of [Cell]s.
+///
+/// Everything displayed on the screen is drawn into the back buffer, then [FrameBuffer::flush] compares it
+/// with the front buffer (what is currently on the screen) and puts only cells which changed to the [Backend].
+pub struct FrameBuffer {
+    /// Size of the buffer, positions are 1-based like terminal ones so this is also the maximum [Position]
+    size: Position,
+    /// Cells which will be displayed on next flush
+    back: Vec<Cell>,
+    /// Cells currently displayed on the screen
+    front: Vec<Cell>,
+    /// Screen needs to be cleared before next flush
+    needs_clear: bool,
+    /// Background [Color] of cells which do not have their own one
+    background: Option<Color>,
+}
+
+impl FrameBuffer {
+    /// Create new empty [FrameBuffer] of `size`
+    pub fn new(size: Position) -> Self {
+        let cells = usize::from(size.x()) * usize::from(size.y());
+        Self {
+            size,
+            back: vec![Cell::default(); cells],
+            front: vec![Cell::default(); cells],
+            needs_clear: false,
+            background: None,
+        }
+    }
+
+    /// Size of the buffer
+    pub fn size(&self) -> Position {
+        self.size
+    }
+
+    /// Resize buffer to new `size`, everything is cleared
+    pub fn resize(&mut self, size: Position) {
+        let background = self.background.take();
+        *self = Self::new(size);
+        self.set_background(background);
+        self.needs_clear = true;
+    }
+
+    /// Background [Color] of the whole buffer
+    pub fn background(&self) -> Option<&Color> {
+        self.background.as_ref()
+    }
+
+    /// Set `background` [Color] of the whole buffer, [None] means terminal default
+    ///
+    /// Cells which were drawn on the previous background are redrawn on the next flush.
+    pub fn set_background(&mut self, background: Option<Color>) {
+        for cell in self.back.iter_mut() {
+            if cell.style.background == self.background {
+                cell.style.background = background.clone();
+            }
+        }
+        self.background = background;
+    }
+
+    /// Index of `position` in cell vectors or [None] if `position` is outside of the buffer
+    fn index(&self, position: &Position) -> Option<usize> {
+        if position.x() == 0 || position.y() == 0 || position.is_out_of_bounds(&self.size) {
+            return None;
+        }
+        Some(
+            usize::from(position.y() - 1) * usize::from(self.size.x())
+                + usize::from(position.x() - 1),
+        )
+    }
+
+    /// [Position] of cell with `index` in cell vectors
+    fn position(&self, index: usize) -> Position {
+        let width = usize::from(self.size.x());
+        Position::new((index % width) as u16 + 1, (index / width) as u16 + 1)
+    }
+
+    /// Put `cell` in `position` of back buffer, positions outside of the buffer are ignored
+    ///
+    /// Wide symbol also covers the next cell, it is replaced with space if there is no next cell in the row.
+    pub fn put(&mut self, position: &Position, cell: Cell) {
+        let Some(index) = self.index(position) else {
+            return;
+        };
+        let mut cell = match cell.style.background {
+            Some(_) => cell,
+            None => cell.with_background(self.background.clone()),
+        };
+        let has_next = position.x() < self.size.x();
+        if cell.is_wide() && !has_next {
+            cell.symbol = " ".into();
+        }
+        self.break_wide(index);
+        if cell.is_wide() {
+            self.break_wide(index + 1);
+            self.back[index + 1] = Cell {
+                symbol: String::new(),
+                style: cell.style.clone(),
+            };
+        }
+        self.back[index] = cell;
+    }
+
+    /// Replace wide symbol covering cell with `index` with blank cells, before something else is put there
+    fn break_wide(&mut self, index: usize) {
+        let column = index % usize::from(self.size.x());
+        let other = if self.back[index].is_continuation() && column > 0 {
+            index - 1
+        } else if self.back[index].is_wide() && column + 1 < usize::from(self.size.x()) {
+            index + 1
+        } else {
+            return;
+        };
+        self.back[other] = Cell::blank(self.background.clone());
+    }
+
+    /// Get [Cell] from back buffer
+    pub fn get(&self, position: &Position) -> Option<&Cell> {
+        self.index(position).map(|index| &self.back[index])
+    }
+
+    /// Clear both buffers, screen will be cleared and painted with the background on next flush
+    pub fn clear(&mut self) {
+        self.back.fill(Cell::blank(self.background.clone()));
+        self.front.fill(Cell::default());
+        self.needs_clear = true;
+    }
+
+    /// Put cells which differ between back and front buffer to `backend` and flush it
+    ///
+    /// Style is only set when it changes between consecutive changed cells.
+    /// Cells covered by wide symbols are not written, writing the symbol covers them.
+    ///
+    /// Returns number of cells written.
+    pub fn flush(&mut self, backend: &mut dyn Backend) -> io::Result<usize> {
+        if self.needs_clear {
+            backend.clear()?;
+            self.needs_clear = false;
+        }
+        let mut written = 0;
+        let mut current_style: Option<&Style> = None;
+        for (index, (back, front)) in self.back.iter().zip(self.front.iter()).enumerate() {
+            if back == front || back.is_continuation() {
+                continue;
+            }
+            if current_style != Some(&back.style) {
+                backend.set_style(&back.style)?;
+                current_style = Some(&back.style);
+            }
+            backend.put_cell(&self.position(index), &back.symbol)?;
+            written += 1;
+        }
+        backend.flush()?;
+        self.front.clone_from(&self.back);
+        Ok(written)
+    }
+
+    /// Same as [FrameBuffer::flush], but with `overlay` cells drawn over the back buffer
+    ///
+    /// Overlay is not kept in the back buffer, so cells under it are displayed again by the first flush without it.
+    pub fn flush_with_overlay(
+        &mut self,
+        overlay: &[(Position, Cell)],
+        backend: &mut dyn Backend,
+    ) -> io::Result<usize> {
+        if overlay.is_empty() {
+            return self.flush(backend);
+        }
+        let back = self.back.clone();
+        for (position, cell) in overlay {
+            self.put(position, cell.clone());
+        }
+        let written = self.flush(backend);
+        self.back = back;
+        written
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::{MemoryBackend, TermionBackend};
+    use termion::cursor;
+
+    #[test]
+    fn put_and_get() {
+        let mut frame = FrameBuffer::new(Position::new(4, 3));
+        let cell = Cell::new('a', Color::Palette(2));
+        frame.put(&Position::new(4, 3), cell.clone());
+        assert_eq!(frame.get(&Position::new(4, 3)), Some(&cell));
+        assert_eq!(frame.get(&Position::new(1, 1)), Some(&Cell::default()));
+        assert_eq!(frame.get(&Position::new(5, 1)), None);
+        assert_eq!(frame.get(&Position::new(0, 1)), None);
+        // out of bounds is ignored
+        frame.put(&Position::new(1, 4), cell);
+    }
+
+    #[test]
+    fn flush_only_changed_cells() {
+        let size = Position::new(10, 5);
+        let mut frame = FrameBuffer::new(size);
+        let mut backend = MemoryBackend::new(size);
+        frame.put(&Position::new(2, 2), Cell::new('a', Color::Palette(1)));
+        assert_eq!(frame.flush(&mut backend).unwrap(), 1);
+        assert_eq!(frame.flush(&mut backend).unwrap(), 0);
+
+        frame.put(&Position::new(2, 2), Cell::new('b', Color::Palette(1)));
+        assert_eq!(frame.flush(&mut backend).unwrap(), 1);
+        assert_eq!(
+            backend.cell(&Position::new(2, 2)),
+            Some(&Cell::new('b', Color::Palette(1)))
+        );
+        assert_eq!(backend.frames(), 3);
+    }
+
+    #[test]
+    fn flush_coalesces_cursor_and_color() {
+        let size = Position::new(10, 5);
+        let mut frame = FrameBuffer::new(size);
+        let mut backend = TermionBackend::new(Vec::new(), size);
+        frame.put(&Position::new(3, 1), Cell::new('a', Color::Palette(1)));
+        frame.put(&Position::new(4, 1), Cell::new('b', Color::Palette(1)));
+        frame.put(&Position::new(5, 1), Cell::new('c', Color::Palette(2)));
+        assert_eq!(frame.flush(&mut backend).unwrap(), 3);
+        assert_eq!(
+            String::from_utf8(backend.get_ref().clone()).unwrap(),
+            format!(
+                "{}{}{}ab{}{}c",
+                style::Reset,
+                Color::Palette(1).get_ansi_string(),
+                cursor::Goto(3, 1),
+                style::Reset,
+                Color::Palette(2).get_ansi_string(),
+            )
+        );
+    }
+
+    #[test]
+    fn clear_resets_buffers() {
+        let size = Position::new(10, 5);
+        let mut frame = FrameBuffer::new(size);
+        let mut backend = MemoryBackend::new(size);
+        frame.put(&Position::new(3, 1), Cell::new('a', Color::Palette(1)));
+        frame.flush(&mut backend).unwrap();
+        frame.clear();
+        assert_eq!(frame.get(&Position::new(3, 1)), Some(&Cell::default()));
+        assert_eq!(frame.flush(&mut backend).unwrap(), 0);
+        assert_eq!(backend.cell(&Position::new(3, 1)), Some(&Cell::default()));
+    }
+
+    #[test]
+    fn resize() {
+        let mut frame = FrameBuffer::new(Position::new(10, 5));
+        let mut backend = MemoryBackend::new(Position::new(10, 5));
+        frame.put(&Position::new(3, 1), Cell::new('a', Color::Palette(1)));
+        frame.flush(&mut backend).unwrap();
+        frame.resize(Position::new(4, 8));
+        assert_eq!(frame.size(), Position::new(4, 8));
+        assert_eq!(frame.get(&Position::new(3, 1)), Some(&Cell::default()));
+        assert_eq!(frame.get(&Position::new(4, 8)), Some(&Cell::default()));
+        assert_eq!(frame.get(&Position::new(5, 1)), None);
+        frame.flush(&mut backend).unwrap();
+        assert_eq!(backend.cell(&Position::new(3, 1)), Some(&Cell::default()));
+    }
+
+    #[test]
+    fn wide_symbols_cover_two_cells() {
+        let size = Position::new(4, 1);
+        let mut frame = FrameBuffer::new(size);
+        let mut backend = MemoryBackend::new(size);
+        frame.put(&Position::new(1, 1), Cell::new('ア', Color::Palette(2)));
+        assert!(frame.get(&Position::new(2, 1)).unwrap().is_continuation());
+        // does not fit in the last column
+        frame.put(&Position::new(4, 1), Cell::new('ア', Color::Palette(2)));
+        assert_eq!(frame.get(&Position::new(4, 1)).unwrap().symbol, " ");
+        assert_eq!(frame.flush(&mut backend).unwrap(), 2);
+        assert_eq!(backend.text(), "ア  ");
+
+        // overwriting second half breaks the wide symbol
+        frame.put(&Position::new(2, 1), Cell::new('b', Color::Palette(2)));
+        assert_eq!(frame.get(&Position::new(1, 1)), Some(&Cell::default()));
+        frame.flush(&mut backend).unwrap();
+        assert_eq!(backend.text(), " b  ");
+
+        frame.put(&Position::new(2, 1), Cell::new('漢', Color::Palette(2)));
+        frame.put(&Position::new(3, 1), Cell::new('c', Color::Palette(2)));
+        assert_eq!(frame.get(&Position::new(2, 1)), Some(&Cell::default()));
+        frame.flush(&mut backend).unwrap();
+        assert_eq!(backend.text(), "  c ");
+    }
+
+    #[test]
+    fn background_fills_screen_and_clear() {
+        let size = Position::new(4, 2);
+        let background = Some(Color::Palette(4));
+        let mut frame = FrameBuffer::new(size);
+        let mut backend = MemoryBackend::new(size);
+        frame.put(&Position::new(2, 1), Cell::new('a', Color::Palette(1)));
+        frame.put(
+            &Position::new(3, 1),
+            Cell::new('b', Color::Palette(1)).with_background(Some(Color::Palette(3))),
+        );
+        frame.set_background(background.clone());
+        assert_eq!(frame.flush(&mut backend).unwrap(), 8);
+        assert_eq!(backend.cell(&Position::new(1, 2)), Some(&Cell::blank(background.clone())));
+        assert_eq!(
+            backend.cell(&Position::new(2, 1)),
+            Some(&Cell::new('a', Color::Palette(1)).with_background(background.clone()))
+        );
+        // own background is kept
+        assert_eq!(
+            backend.cell(&Position::new(3, 1)).unwrap().style.background,
+            Some(Color::Palette(3))
+        );
+        frame.put(&Position::new(4, 2), Cell::new('c', Color::Palette(1)));
+        assert_eq!(frame.get(&Position::new(4, 2)).unwrap().style.background, background);
+        frame.clear();
+        assert_eq!(frame.flush(&mut backend).unwrap(), 8);
+        assert_eq!(backend.cell(&Position::new(3, 1)), Some(&Cell::blank(background)));
+    }
+}
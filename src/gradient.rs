@@ -0,0 +1,166 @@
+use crate::colors::Color;
+use crate::error::Error;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// How color changes between two [GradientStop]s
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum Easing {
+    /// Color changes evenly
+    #[default]
+    Linear,
+    /// Color changes slowly near the first stop and quickly near the second one
+    Exponential,
+}
+
+impl Easing {
+    /// Apply easing to `t` (0.0 - 1.0)
+    fn apply(&self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::Exponential if t <= 0.0 => 0.0,
+            Easing::Exponential => 2f32.powf(10.0 * t - 10.0),
+        }
+    }
+}
+
+/// Single color stop of [Gradient]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct GradientStop {
+    /// Position along the trail in percent, 0 is next to the head and 100 is the end of the trail
+    pub position: u8,
+    /// [Color] in this position
+    pub color: Color,
+}
+
+/// Colors of the trail interpolated between [GradientStop]s
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct Gradient {
+    /// Color stops, sorted by position, see [Gradient::validate]
+    pub stops: Vec<GradientStop>,
+    /// [Easing] used between stops
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+impl Gradient {
+    /// Parse [Gradient] from `stops` in `position:color;position:color...` syntax, e.g. `0:200,255,200;100:0,80,0`
+    ///
+    /// Position is in percent, color uses syntax of [Color::parse]
+    pub fn parse(stops: &str, easing: Easing) -> Result<Self, Error> {
+        let invalid = || {
+            Error::InvalidColor(format!(
+                "'{stops}' is not a gradient, use position:color;position:color syntax e.g.: 0:200,255,200;100:0,80,0"
+            ))
+        };
+        let mut stops = stops
+            .split(';')
+            .map(|stop| {
+                let (position, color) = stop.split_once(':').ok_or_else(invalid)?;
+                let position = match position.trim().parse::<u8>() {
+                    Ok(position @ 0..=100) => position,
+                    _ => return Err(invalid()),
+                };
+                let color = Color::parse(color)?;
+                Ok(GradientStop { position, color })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        stops.sort_by_key(|stop| stop.position);
+        Ok(Self { stops, easing })
+    }
+
+    /// Check [Gradient] loaded from config or theme file and sort its stops by position
+    ///
+    /// Returns [Error::InvalidColor] if there are no stops or any position is above 100
+    pub fn validate(&mut self) -> Result<(), Error> {
+        if self.stops.is_empty() {
+            return Err(Error::InvalidColor("gradient needs at least one color stop".into()));
+        }
+        if let Some(stop) = self.stops.iter().find(|stop| stop.position > 100) {
+            return Err(Error::InvalidColor(format!(
+                "gradient stop position {} is out of range, use value from 0 to 100",
+                stop.position
+            )));
+        }
+        self.stops.sort_by_key(|stop| stop.position);
+        Ok(())
+    }
+
+    /// Get [Color] at `t` (0.0 - 1.0) along the trail
+    ///
+    /// Before the first and after the last stop their colors are used
+    pub fn color_at(&self, t: f32) -> Color {
+        let position = t.clamp(0.0, 1.0) * 100.0;
+        let next = self
+            .stops
+            .iter()
+            .position(|stop| f32::from(stop.position) >= position);
+        let (from, to) = match next {
+            None => match self.stops.last() {
+                Some(last) => return last.color.clone(),
+                None => return Color::Palette(7),
+            },
+            Some(0) => return self.stops[0].color.clone(),
+            Some(next) => (&self.stops[next - 1], &self.stops[next]),
+        };
+        let span = f32::from(to.position - from.position);
+        let t = self
+            .easing
+            .apply((position - f32::from(from.position)) / span);
+        let (from, to) = (from.color.to_rgb(), to.color.to_rgb());
+        let mix = |from: u8, to: u8| (f32::from(from) + (f32::from(to) - f32::from(from)) * t).round() as u8;
+        Color::RGB {
+            r: mix(from.0, to.0),
+            g: mix(from.1, to.1),
+            b: mix(from.2, to.2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let gradient = Gradient::parse("100:0,80,0; 0:2;50:200", Easing::Linear).unwrap();
+        let positions: Vec<_> = gradient.stops.iter().map(|stop| stop.position).collect();
+        assert_eq!(positions, [0, 50, 100]);
+        assert_eq!(gradient.stops[0].color, Color::Palette(2));
+        assert_eq!(gradient.stops[1].color, Color::Ansi256(200));
+        assert_eq!(gradient.stops[2].color, Color::RGB { r: 0, g: 80, b: 0 });
+        assert!(Gradient::parse("101:1", Easing::Linear).is_err());
+        assert!(Gradient::parse("10", Easing::Linear).is_err());
+        assert!(Gradient::parse("10:1,2", Easing::Linear).is_err());
+    }
+
+    #[test]
+    fn validate_loaded_gradient() {
+        let yaml = "stops:\n- {position: 100, color: '#000000'}\n- {position: 0, color: '#c8c8c8'}\n";
+        let mut gradient: Gradient = serde_yaml::from_str(yaml).unwrap();
+        gradient.validate().unwrap();
+        assert_eq!(gradient.color_at(0.0), Color::RGB { r: 200, g: 200, b: 200 });
+        assert_eq!(gradient.color_at(0.5), Color::RGB { r: 100, g: 100, b: 100 });
+
+        let mut gradient: Gradient = serde_yaml::from_str("stops: [{position: 101, color: 2}]").unwrap();
+        assert!(gradient.validate().is_err());
+        let mut gradient: Gradient = serde_yaml::from_str("stops: []").unwrap();
+        assert!(gradient.validate().is_err());
+    }
+
+    #[test]
+    fn color_at() {
+        let gradient = Gradient::parse("20:0,0,0;60:200,100,0", Easing::Linear).unwrap();
+        assert_eq!(gradient.color_at(0.0), Color::RGB { r: 0, g: 0, b: 0 });
+        assert_eq!(gradient.color_at(0.4), Color::RGB { r: 100, g: 50, b: 0 });
+        assert_eq!(gradient.color_at(1.0), Color::RGB { r: 200, g: 100, b: 0 });
+    }
+
+    #[test]
+    fn exponential_easing() {
+        let gradient = Gradient::parse("0:0,0,0;100:255,255,255", Easing::Exponential).unwrap();
+        assert_eq!(gradient.color_at(0.0), Color::RGB { r: 0, g: 0, b: 0 });
+        assert_eq!(gradient.color_at(0.5), Color::RGB { r: 8, g: 8, b: 8 });
+        assert_eq!(gradient.color_at(1.0), Color::RGB { r: 255, g: 255, b: 255 });
+    }
+}
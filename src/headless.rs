@@ -0,0 +1,200 @@
+use crate::backend::{Backend, MemoryBackend, TeeBackend, TermionBackend};
+use crate::colors::ColorDepth;
+use crate::config::Config;
+use crate::error::Error;
+use crate::faller_adder::FallerAdder;
+use crate::frame_buffer::FrameBuffer;
+use crate::recorder::{AsciicastWriter, RecordClock, Recorder};
+use crate::residue::Residue;
+use crate::rng::new_rng;
+use crate::render_frame;
+use clap::ValueEnum;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// Format of frames written in headless mode
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum DumpFormat {
+    /// Plain text, only characters
+    Text,
+    /// Text with ANSI escape codes for colors
+    Ansi,
+}
+
+/// Receives every frame rendered in headless mode, e.g. to save it as image
+pub trait FrameExporter {
+    /// Export current contents of `backend` as next frame
+    fn add_frame(&mut self, backend: &MemoryBackend) -> Result<(), Error>;
+    /// Called after the last frame, finishes and flushes exported files
+    fn finish(&mut self) -> Result<(), Error>;
+}
+
+/// Runs simulation without terminal and dumps rendered frames
+pub struct Headless {
+    /// Number of frames to simulate
+    pub frames: usize,
+    /// [DumpFormat] of written frames
+    pub format: DumpFormat,
+    /// Write only the last frame instead of every one
+    pub last_frame_only: bool,
+    /// Path of asciicast file to record frames to, see [Recorder]
+    pub record: Option<String>,
+    /// [ColorDepth] used in the recording
+    pub color_depth: ColorDepth,
+    /// [FrameExporter]s which receive every frame
+    pub exporters: Vec<Box<dyn FrameExporter>>,
+}
+
+impl Headless {
+    /// Simulate `frames` frames using `config` and write them to `out`, frames are separated by empty line
+    ///
+    /// Every simulation step is one frame, none are dropped
+    ///
+    /// Without `out` frames are only passed to [FrameExporter]s
+    pub fn run(&mut self, config: Config, mut out: Option<&mut dyn Write>) -> Result<(), Error> {
+        let size = *config.screen_size();
+        let background = config.colors().background.clone();
+        let frame_duration = config.frame_duration();
+        let mut residue = Residue::new(size, *config.decay(), config.chars_to_use(), *config.fps());
+        let rng = new_rng(*config.seed());
+        let mut faller_adder = FallerAdder::new(Rc::new(RefCell::new(config)), Rc::clone(&rng));
+        let mut frame = FrameBuffer::new(size);
+        frame.set_background(background);
+        let mut backend = MemoryBackend::new(size);
+        let mut recording = match self.record {
+            Some(ref path) => {
+                let cast = AsciicastWriter::create(path, size)?;
+                let clock = RecordClock::frames(frame_duration);
+                Some(
+                    TermionBackend::init(Recorder::new(io::sink(), cast, clock), size)?
+                        .with_color_depth(self.color_depth),
+                )
+            }
+            None => None,
+        };
+
+        for frame_no in 1..=self.frames {
+            let mut tee;
+            let output: &mut dyn Backend = match recording {
+                Some(ref mut recording) => {
+                    tee = TeeBackend(&mut backend, recording);
+                    &mut tee
+                }
+                None => &mut backend,
+            };
+            render_frame(
+                &mut faller_adder.falling_chars.borrow_mut(),
+                &mut *rng.borrow_mut(),
+                &mut frame,
+                &mut residue,
+                output,
+            )?;
+            faller_adder.add_and_retire()?;
+            if !self.last_frame_only || frame_no == self.frames {
+                for exporter in self.exporters.iter_mut() {
+                    exporter.add_frame(&backend)?;
+                }
+                if let Some(ref mut out) = out {
+                    if frame_no > 1 && !self.last_frame_only {
+                        writeln!(out)?;
+                    }
+                    self.write_frame(&backend, *out)?;
+                }
+            }
+        }
+        for exporter in self.exporters.iter_mut() {
+            exporter.finish()?;
+        }
+        if let Some(out) = out {
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write current contents of `backend` to `out` using configured [DumpFormat]
+    fn write_frame(&self, backend: &MemoryBackend, out: &mut dyn Write) -> Result<(), Error> {
+        let text = match self.format {
+            DumpFormat::Text => backend.text(),
+            DumpFormat::Ansi => backend.ansi_text(),
+        };
+        writeln!(out, "{text}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::position::Position;
+
+    fn run(mut headless: Headless) -> String {
+        let mut config = Config::default();
+        config.set_screen_size(Position::new(20, 6));
+        let mut out = Vec::new();
+        headless.run(config, Some(&mut out)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn writes_every_frame() {
+        let output = run(Headless {
+            frames: 3,
+            format: DumpFormat::Text,
+            last_frame_only: false,
+            record: None,
+            color_depth: ColorDepth::TrueColor,
+            exporters: Vec::new(),
+        });
+        // 3 frames of 6 lines and 2 separating lines
+        assert_eq!(output.lines().count(), 3 * 6 + 2);
+        assert!(output.lines().all(|line| line.is_empty() || line.chars().count() == 20));
+    }
+
+    #[test]
+    fn writes_last_frame_only() {
+        let output = run(Headless {
+            frames: 3,
+            format: DumpFormat::Ansi,
+            last_frame_only: true,
+            record: None,
+            color_depth: ColorDepth::TrueColor,
+            exporters: Vec::new(),
+        });
+        assert_eq!(output.lines().count(), 6);
+        assert!(output.contains('\u{1b}'));
+    }
+
+    /// Counts exported frames
+    struct CountingExporter(Rc<RefCell<(usize, bool)>>);
+
+    impl FrameExporter for CountingExporter {
+        fn add_frame(&mut self, _backend: &MemoryBackend) -> Result<(), Error> {
+            self.0.borrow_mut().0 += 1;
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<(), Error> {
+            self.0.borrow_mut().1 = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn passes_frames_to_exporters() {
+        let counter = Rc::new(RefCell::new((0, false)));
+        let mut config = Config::default();
+        config.set_screen_size(Position::new(20, 6));
+        Headless {
+            frames: 4,
+            format: DumpFormat::Text,
+            last_frame_only: false,
+            record: None,
+            color_depth: ColorDepth::TrueColor,
+            exporters: vec![Box::new(CountingExporter(Rc::clone(&counter)))],
+        }
+        .run(config, None)
+        .unwrap();
+        assert_eq!(*counter.borrow(), (4, true));
+    }
+}
@@ -0,0 +1,261 @@
+use crate::backend::{Backend, MemoryBackend};
+use crate::bitmap_font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::error::Error;
+use crate::headless::FrameExporter;
+use crate::position::{Position, PositionTrait};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Background of rasterized frames
+const BACKGROUND: (u8, u8, u8) = (0, 0, 0);
+/// Color of cells which use terminal default color
+const DEFAULT_FOREGROUND: (u8, u8, u8) = (204, 204, 204);
+
+/// Frame rasterized to RGB pixels using [bitmap_font]
+pub struct Raster {
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+    /// Pixels row by row, 3 bytes (red, green, blue) per pixel
+    pub pixels: Vec<u8>,
+}
+
+impl Raster {
+    /// Rasterize contents of `backend`, each cell becomes one glyph of [bitmap_font]
+    ///
+    /// Bold cells are drawn twice, second time moved by one pixel to the right
+    pub fn from_backend(backend: &MemoryBackend) -> Self {
+        let size = backend.size();
+        let width = usize::from(size.x()) * GLYPH_WIDTH;
+        let height = usize::from(size.y()) * GLYPH_HEIGHT;
+        let mut pixels = Vec::with_capacity(width * height * 3);
+        for _ in 0..width * height {
+            pixels.extend([BACKGROUND.0, BACKGROUND.1, BACKGROUND.2]);
+        }
+        let mut raster = Self {
+            width,
+            height,
+            pixels,
+        };
+        for y in 1..=size.y() {
+            for x in 1..=size.x() {
+                if let Some(cell) = backend.cell(&Position::new(x, y)) {
+                    if let Some(ref background) = cell.style.background {
+                        raster.fill_cell(x, y, background.to_rgb());
+                    }
+                    let color = cell
+                        .style
+                        .color
+                        .as_ref()
+                        .map_or(DEFAULT_FOREGROUND, |color| color.to_rgb());
+                    // built-in font has no combining marks, so only the first character is drawn
+                    if let Some(ch) = cell.symbol.chars().next() {
+                        raster.draw_glyph(x, y, ch, color, cell.style.bold);
+                    }
+                }
+            }
+        }
+        raster
+    }
+
+    /// Fill whole cell with 1-based `x` and `y` with `color`
+    fn fill_cell(&mut self, x: u16, y: u16, color: (u8, u8, u8)) {
+        let left = usize::from(x - 1) * GLYPH_WIDTH;
+        let top = usize::from(y - 1) * GLYPH_HEIGHT;
+        for row in top..top + GLYPH_HEIGHT {
+            for column in left..left + GLYPH_WIDTH {
+                let index = (row * self.width + column) * 3;
+                self.pixels[index..index + 3].copy_from_slice(&[color.0, color.1, color.2]);
+            }
+        }
+    }
+
+    /// Draw glyph of `ch` in cell with 1-based `x` and `y`
+    fn draw_glyph(&mut self, x: u16, y: u16, ch: char, color: (u8, u8, u8), bold: bool) {
+        let glyph = bitmap_font::glyph(ch);
+        let left = usize::from(x - 1) * GLYPH_WIDTH;
+        let top = usize::from(y - 1) * GLYPH_HEIGHT;
+        for row in 0..GLYPH_HEIGHT {
+            for column in 0..GLYPH_WIDTH {
+                let set = bitmap_font::is_set(glyph, column, row)
+                    || (bold && column > 0 && bitmap_font::is_set(glyph, column - 1, row));
+                if set {
+                    let index = ((top + row) * self.width + left + column) * 3;
+                    self.pixels[index..index + 3].copy_from_slice(&[color.0, color.1, color.2]);
+                }
+            }
+        }
+    }
+}
+
+/// Convert encoder error into [Error]
+fn encoding_error(error: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::Io(io::Error::other(error))
+}
+
+/// Size of [Raster] of `size` cells, which needs to fit in [u16] for GIF
+fn gif_size(size: Position) -> Result<(u16, u16), Error> {
+    let width = u16::try_from(usize::from(size.x()) * GLYPH_WIDTH);
+    let height = u16::try_from(usize::from(size.y()) * GLYPH_HEIGHT);
+    match (width, height) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(Error::ConfigParse(format!(
+            "screen of {}x{} is too big to be exported as GIF",
+            size.x(),
+            size.y()
+        ))),
+    }
+}
+
+/// Exports frames as animated GIF
+pub struct GifExporter {
+    /// GIF encoder, taken when export is finished
+    encoder: Option<gif::Encoder<BufWriter<File>>>,
+    /// Size of frames in pixels
+    size: (u16, u16),
+    /// Delay between frames in hundredths of a second
+    delay: u16,
+}
+
+impl GifExporter {
+    /// Create GIF file in `path` for frames of `size` cells, displayed every `frame_duration_ms`
+    pub fn create(path: &str, size: Position, frame_duration_ms: u64) -> Result<Self, Error> {
+        let size = gif_size(size)?;
+        let mut encoder =
+            gif::Encoder::new(BufWriter::new(File::create(path)?), size.0, size.1, &[])
+                .map_err(encoding_error)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(encoding_error)?;
+        Ok(Self {
+            encoder: Some(encoder),
+            size,
+            delay: u16::try_from(frame_duration_ms / 10).unwrap_or(u16::MAX),
+        })
+    }
+}
+
+impl FrameExporter for GifExporter {
+    fn add_frame(&mut self, backend: &MemoryBackend) -> Result<(), Error> {
+        let raster = Raster::from_backend(backend);
+        // exact palette if possible, it is much faster and lossless, quantize otherwise
+        let mut palette: HashMap<&[u8], u8> = HashMap::new();
+        let mut indices = Vec::with_capacity(raster.width * raster.height);
+        for pixel in raster.pixels.chunks(3) {
+            let next = palette.len();
+            if next > usize::from(u8::MAX) && !palette.contains_key(pixel) {
+                indices.clear();
+                break;
+            }
+            indices.push(*palette.entry(pixel).or_insert(next as u8));
+        }
+        let mut frame = if indices.len() == raster.width * raster.height {
+            let mut colors = vec![0u8; palette.len() * 3];
+            for (pixel, index) in palette {
+                let index = usize::from(index) * 3;
+                colors[index..index + 3].copy_from_slice(pixel);
+            }
+            gif::Frame::from_palette_pixels(self.size.0, self.size.1, indices, colors, None)
+        } else {
+            gif::Frame::from_rgb_speed(self.size.0, self.size.1, &raster.pixels, 10)
+        };
+        frame.delay = self.delay;
+        match self.encoder {
+            Some(ref mut encoder) => encoder.write_frame(&frame).map_err(encoding_error),
+            None => Ok(()),
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if let Some(encoder) = self.encoder.take() {
+            // writes GIF trailer
+            encoder.into_inner().map_err(encoding_error)?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Exports frames as numbered PNG files
+pub struct PngExporter {
+    /// Beginning of path of each file, frame number and extension are added to it
+    prefix: String,
+    /// Number of frames exported so far
+    frames: usize,
+}
+
+impl PngExporter {
+    /// Create [PngExporter] writing files `<prefix>00001.png`, `<prefix>00002.png`...
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+            frames: 0,
+        }
+    }
+}
+
+impl FrameExporter for PngExporter {
+    fn add_frame(&mut self, backend: &MemoryBackend) -> Result<(), Error> {
+        let raster = Raster::from_backend(backend);
+        self.frames += 1;
+        let path = format!("{}{:05}.png", self.prefix, self.frames);
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            raster.width as u32,
+            raster.height as u32,
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(encoding_error)?;
+        writer
+            .write_image_data(&raster.pixels)
+            .map_err(encoding_error)?;
+        writer.finish().map_err(encoding_error)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::colors::Color;
+    use crate::frame_buffer::Style;
+
+    #[test]
+    fn rasterize_cells() {
+        let mut backend = MemoryBackend::new(Position::new(2, 1));
+        backend
+            .set_style(&Style {
+                color: Some(Color::RGB { r: 1, g: 2, b: 3 }),
+                bold: false,
+                background: None,
+            })
+            .unwrap();
+        backend.put_cell(&Position::new(2, 1), "A").unwrap();
+        let raster = Raster::from_backend(&backend);
+        assert_eq!(raster.width, 2 * GLYPH_WIDTH);
+        assert_eq!(raster.height, GLYPH_HEIGHT);
+        let pixel = |x: usize, y: usize| {
+            let index = (y * raster.width + x) * 3;
+            &raster.pixels[index..index + 3]
+        };
+        // top of 'A' in second cell
+        assert_eq!(pixel(GLYPH_WIDTH + 2, 2), &[1, 2, 3]);
+        assert_eq!(pixel(GLYPH_WIDTH + 1, 2), &[0, 0, 0]);
+        // first cell is empty
+        assert!((0..GLYPH_HEIGHT).all(|y| (0..GLYPH_WIDTH).all(|x| pixel(x, y) == [0, 0, 0])));
+    }
+
+    #[test]
+    fn gif_size_limit() {
+        assert_eq!(
+            gif_size(Position::new(80, 24)).unwrap(),
+            (80 * GLYPH_WIDTH as u16, 24 * GLYPH_HEIGHT as u16)
+        );
+        assert!(gif_size(Position::new(20000, 24)).is_err());
+    }
+}
@@ -1,120 +1,197 @@
 //! Library used for ruMatrix
 #![warn(missing_docs)]
 
+/// [Backend] module
+pub mod backend;
+/// Built-in bitmap font module
+pub mod bitmap_font;
+/// Built-in character sets module
+pub mod charset;
 /// [Colors] and [Color] module
 pub mod colors;
 /// [Config] module
 pub mod config;
+/// [ConfigWatcher] module
+pub mod config_watcher;
+/// [ControlSocket] module
+pub mod control_socket;
+/// Runtime [Controls] module
+pub mod controls;
+/// [Error] module
+pub mod error;
 /// [FallerAdder] module
 pub mod faller_adder;
 /// [FallingChar] module
 pub mod falling_char;
+/// [FrameBuffer] module
+pub mod frame_buffer;
+/// [Gradient] module
+pub mod gradient;
+/// [Headless] mode module
+pub mod headless;
+/// [GifExporter] and [PngExporter] module
+pub mod image_export;
 /// [Message] module
 pub mod message;
+/// [Oklab] color space module
+pub mod oklab;
+/// CSS/X11 named colors module
+pub mod named_colors;
 /// [Position] module
 pub mod position;
 /// [RandomVecBag] module
 pub mod random_vec_bag;
+/// Random number generator module
+pub mod rng;
+/// [Recorder] module
+pub mod recorder;
+/// [Residue] of characters left behind module
+pub mod residue;
+/// [Theme] module
+pub mod theme;
+/// Fixed [Timestep] module
+pub mod timestep;
+/// Display width of Unicode text module
+pub mod unicode;
+/// [WebExporter] module
+pub mod web_export;
+use crate::backend::{Backend, TermionBackend};
+use crate::colors::ColorDepth;
 use crate::config::{Cli, Config};
+use crate::config_watcher::{ConfigWatcher, CHECK_INTERVAL};
+use crate::control_socket::ControlSocket;
+use crate::controls::Controls;
+use crate::error::Error;
 use crate::faller_adder::FallerAdder;
 use crate::falling_char::*;
+use crate::frame_buffer::FrameBuffer;
+use crate::headless::{FrameExporter, Headless};
+use crate::image_export::{GifExporter, PngExporter};
+use crate::recorder::{AsciicastWriter, RecordClock, Recorder};
+use crate::residue::Residue;
+use crate::rng::new_rng;
+use crate::theme::{Theme, BUILTIN_THEMES};
+use crate::timestep::Timestep;
+use crate::web_export::{WebExporter, WebFormat};
 
 use std::cell::RefCell;
-use std::fs;
-use std::io::Read;
+use std::fs::File;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::SystemTime;
+use std::sync::Arc;
 
 use clap::Parser;
 use position::Position;
 use rand::prelude::*;
-use random_vec_bag::RandomVecBag;
-use termion::AsyncReader;
-
+use signal_hook::consts::SIGWINCH;
+use termion::{async_stdin, is_tty, terminal_size};
 use termion::raw::IntoRawMode;
-use termion::screen::IntoAlternateScreen;
-use termion::{async_stdin, clear, cursor, screen::ToMainScreen, style};
+use termion::input::{Keys, TermRead};
+use termion::AsyncReader;
 
-use crate::position::PositionTrait;
-use std::{
-    io::Bytes,
-    io::{self, Write},
-    process,
-};
+use std::io::{self, BufWriter};
 
 // Easiest way to have this parametrized via cli IMHO
 // TODO: Find better way
 static INCLUDE_DEFAULTS_IN_SERIALIZATION: AtomicBool = AtomicBool::new(false);
 
-/// Handle keyboard input
-pub fn handle_keys(stdin: &mut Bytes<AsyncReader>) {
-    let key_char = stdin.next();
-    if let Some(Ok(b'q')) = key_char {
-        clean_exit();
-    }
-    if let Some(Ok(b'c')) = key_char {
-        print!("{}{}", style::Reset, clear::All)
+/// Set when program should exit after current frame
+static QUIT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Handle keyboard input, all pending keys (including escape sequences of e.g. arrows) are passed to `controls`
+pub fn handle_keys(
+    stdin: &mut Keys<AsyncReader>,
+    controls: &mut Controls,
+    frame: &mut FrameBuffer,
+    residue: &mut Residue,
+    faller_adder: &mut FallerAdder,
+    timestep: &mut Timestep,
+) {
+    while let Some(Ok(key)) = stdin.next() {
+        controls.handle_key(&key, frame, residue, faller_adder, timestep);
     }
 }
 
-/// Executed when exiting program, clears screen and shows cursor again
-pub fn clean_exit() {
-    print!(
-        "{}{}{}{}",
-        style::Reset,
-        clear::All,
-        cursor::Show,
-        cursor::Goto(1, 1)
-    );
-    io::stdout().flush().unwrap();
-    process::exit(0);
+/// Request program exit, main loop will stop and terminal will be restored by [Backend] being dropped
+pub fn request_quit() {
+    QUIT_REQUESTED.store(true, Ordering::SeqCst);
 }
 
-/// Main loop of the program
-pub fn main_loop(falling_chars: Rc<RefCell<Vec<FallingChar>>>) {
-    let start_time = SystemTime::now();
-    let mut falling_chars = falling_chars.borrow_mut();
-    let mut screen = io::stdout()
-        .into_raw_mode()
-        .unwrap()
-        .into_alternate_screen()
-        .unwrap();
+/// Do one simulation step: render `residue` and all fallers into `frame` and advance them
+///
+/// Characters left behind by fallers are passed to `residue`
+pub fn update_frame(
+    falling_chars: &mut [FallingChar],
+    rng: &mut dyn RngCore,
+    frame: &mut FrameBuffer,
+    residue: &mut Residue,
+) {
+    residue.render(rng, frame);
+    for f in falling_chars.iter_mut() {
+        f.render(rng, frame);
+        if let Some(position) = f.advance() {
+            if let Some(cell) = frame.get(&position) {
+                residue.leave(&position, cell);
+            }
+        }
+    }
+}
 
-    write!(screen, "{ToMainScreen}").unwrap();
+/// Do one simulation step (see [update_frame]), then put cells which changed since last frame to the `backend`
+pub fn render_frame(
+    falling_chars: &mut [FallingChar],
+    rng: &mut dyn RngCore,
+    frame: &mut FrameBuffer,
+    residue: &mut Residue,
+    backend: &mut dyn Backend,
+) -> Result<(), Error> {
+    update_frame(falling_chars, rng, frame, residue);
+    frame.flush(backend)?;
+    Ok(())
+}
 
-    for f in falling_chars.iter_mut() {
-        f.render(&mut thread_rng(), &mut screen);
-        f.advance();
-    }
-    screen.flush().unwrap(); // flush alternate screen
-    let time_elapsed = SystemTime::now()
-        .duration_since(start_time)
-        .expect("Cannot get elapsed time")
-        .as_millis();
-    let time_to_sleep = if time_elapsed < 33 {
-        // 1/30 second ~= 33 ms
-        33 - time_elapsed as u64
-    } else {
-        1
-    };
-    drop(screen); // copy alternate screen to main screen
-    std::thread::sleep(std::time::Duration::from_millis(time_to_sleep));
+/// Main loop of the program
+///
+/// Does all simulation steps which are due according to `timestep` (see [update_frame]), renders the result once
+/// with help of `controls` over it and waits until the next step is due. When rendering is slow, simulation
+/// catches up and frames in between are dropped.
+pub fn main_loop(
+    faller_adder: &mut FallerAdder,
+    rng: Rc<RefCell<dyn RngCore>>,
+    frame: &mut FrameBuffer,
+    residue: &mut Residue,
+    backend: &mut dyn Backend,
+    timestep: &mut Timestep,
+    controls: &Controls,
+) -> Result<(), Error> {
+    let steps = timestep.tick();
+    for _ in 0..steps {
+        update_frame(
+            &mut faller_adder.falling_chars.borrow_mut(),
+            &mut *rng.borrow_mut(),
+            frame,
+            residue,
+        );
+        faller_adder.add_and_retire()?;
+    }
+    // even without any step help or notice might have changed, flush puts only what changed anyway
+    let overlay = controls.overlay(faller_adder, timestep, frame.size());
+    frame.flush_with_overlay(&overlay, backend)?;
+    std::thread::sleep(timestep.until_next());
+    Ok(())
 }
 
 /// Main function of the program
-pub fn program_main() {
+///
+/// Returns [Error] which stopped the program, see [Error::exit_code]
+pub fn program_main() -> Result<(), Error> {
     let cli = Cli::parse();
 
-    let mut config = match cli.config_file {
-        Some(config_file) => {
-            let config_string = fs::read_to_string(config_file)
-                .expect("Cannot read config file, make sure that specified path to it is correct.");
-            serde_yaml::from_str(&config_string).expect("Incorrect config file contents.")
-        }
-        None => Config::default(),
-    };
-    config.parse_cli();
+    if cli.list_themes {
+        return list_themes(&cli);
+    }
+
+    let config = Config::load(cli.config_file.as_deref(), &cli)?;
 
     if cli.print_full_config {
         println!("# Current config YAML, includes:");
@@ -124,55 +201,310 @@ pub fn program_main() {
         println!("#   Overwritten by settings loaded from config file (if any)");
         println!("#   Overwritten by settings loaded from command line (if any)");
         INCLUDE_DEFAULTS_IN_SERIALIZATION.store(true, Ordering::SeqCst);
-        println!(
-            "{}",
-            serde_yaml::to_string(&config).expect("Cannot serialize current config!")
-        );
-        process::exit(0);
+        println!("{}", serialize_config(&config)?);
+        return Ok(());
     } else if cli.print_config {
         println!("# Current config YAML, includes:");
         println!("#   Settings loaded from config file (if any)");
         println!("#   Overwritten by settings loaded from command line (if any)");
         INCLUDE_DEFAULTS_IN_SERIALIZATION.store(false, Ordering::SeqCst);
-        println!(
-            "{}",
-            serde_yaml::to_string(&config).expect("Cannot serialize current config!")
-        );
-        process::exit(0);
-    }
-
-    ctrlc::set_handler(|| {
-        clean_exit();
-    })
-    .expect("Error handling CTRL+C");
-
-    print!("{}{}{}", clear::All, cursor::Hide, style::Reset);
-    io::stdout().flush().unwrap();
-
-    let falling_chars = Rc::new(RefCell::new(Vec::with_capacity(*config.no_fallers())));
-    let mut vec: Vec<u16> = Vec::with_capacity(usize::from(config.screen_size().x()) * 3);
-    // we want unique positions for fallers, but it still looks cool if some fallers fall at the same time at the same position
-    for _ in 1..=2 {
-        vec.extend(1..=config.screen_size().x());
-    }
-    let mut position_bag = RandomVecBag::new(vec);
-    let mut stdin = async_stdin().bytes();
-    let falling_char_ref1 = Rc::clone(&falling_chars);
-    let mut faller_adder = FallerAdder {
-        rng: Rc::new(RefCell::new(thread_rng())),
-        falling_chars: falling_char_ref1,
-        probability_to_add: 0.22,
-        positions: &mut position_bag,
-        config: &config,
+        println!("{}", serialize_config(&config)?);
+        return Ok(());
+    }
+
+    if cli.headless {
+        let mut exporters: Vec<Box<dyn FrameExporter>> = Vec::new();
+        let frame_duration_ms = config.frame_duration().as_millis() as u64;
+        if let Some(ref path) = cli.gif {
+            let size = *config.screen_size();
+            exporters.push(Box::new(GifExporter::create(
+                path,
+                size,
+                frame_duration_ms,
+            )?));
+        }
+        if let Some(ref prefix) = cli.png {
+            exporters.push(Box::new(PngExporter::new(prefix)));
+        }
+        if !exporters.is_empty() && !config.chars_to_use().chars().all(bitmap_font::has_glyph) {
+            eprintln!("ruMatrix: image export font has only ASCII characters, other ones are drawn as '?'");
+        }
+        for (path, format) in [(&cli.html, WebFormat::Html), (&cli.svg, WebFormat::Svg)] {
+            if let Some(path) = path {
+                exporters.push(Box::new(WebExporter::create(
+                    path,
+                    format,
+                    frame_duration_ms,
+                )?));
+            }
+        }
+        let exporting = !exporters.is_empty();
+        let mut headless = Headless {
+            frames: cli.frames.unwrap_or_default(),
+            format: cli.format,
+            last_frame_only: cli.last_frame_only,
+            record: cli.record.clone(),
+            // there is no terminal to detect color depth of, recording keeps full colors unless asked otherwise
+            color_depth: cli.color_depth.unwrap_or(ColorDepth::TrueColor),
+            exporters,
+        };
+        // text frames go to stdout only when nothing else is exported
+        return match cli.output {
+            Some(ref path) => headless.run(config, Some(&mut BufWriter::new(File::create(path)?))),
+            None if exporting => headless.run(config, None),
+            None => headless.run(config, Some(&mut io::stdout().lock())),
+        };
+    }
+
+    if !is_tty(&io::stdout()) {
+        return Err(Error::NoTerminal);
+    }
+    ctrlc::set_handler(request_quit).map_err(|e| Error::Io(io::Error::other(e)))?;
+
+    let color_depth = cli.color_depth.unwrap_or_else(ColorDepth::detect);
+    let mut backend: Box<dyn Backend> = match cli.record {
+        Some(ref path) => {
+            let size = *config.screen_size();
+            let cast = AsciicastWriter::create(path, size)?;
+            let out = io::stdout().into_raw_mode()?;
+            Box::new(
+                TermionBackend::init(Recorder::new(out, cast, RecordClock::real_time()), size)?
+                    .with_color_depth(color_depth),
+            )
+        }
+        None => Box::new(TermionBackend::stdout()?.with_color_depth(color_depth)),
     };
 
-    loop {
-        let falling_char_ref2 = Rc::clone(&falling_chars);
-        handle_keys(&mut stdin);
-        main_loop(falling_char_ref2);
-        handle_keys(&mut stdin);
-        faller_adder
-            .add_and_retire()
-            .expect("Cannot add/or retire fallers");
+    // forced size should not be changed when terminal is resized
+    let size_forced = cli.size_x.is_some() || cli.size_y.is_some();
+    let resized = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(SIGWINCH, Arc::clone(&resized))?;
+
+    let mut frame = FrameBuffer::new(*config.screen_size());
+    frame.set_background(config.colors().background.clone());
+    let mut residue = Residue::new(*config.screen_size(), *config.decay(), config.chars_to_use(), *config.fps());
+    let mut stdin = async_stdin().keys();
+    let rng = new_rng(*config.seed());
+    let mut timestep = Timestep::new(config.step_duration(), *config.time_scale());
+    let mut controls = Controls::new(&config)?;
+    let mut config_watcher = cli
+        .config_file
+        .as_ref()
+        .map(|path| ConfigWatcher::new(path, CHECK_INTERVAL));
+    let mut control_socket = match cli.control_socket {
+        Some(ref path) => Some(ControlSocket::bind(path)?),
+        None => None,
+    };
+    let mut faller_adder = FallerAdder::new(Rc::new(RefCell::new(config)), Rc::clone(&rng));
+
+    while !QUIT_REQUESTED.load(Ordering::SeqCst) {
+        if resized.swap(false, Ordering::SeqCst) && !size_forced {
+            if let Ok((x, y)) = terminal_size() {
+                let size = Position::new(x, y);
+                backend.resize(size);
+                frame.resize(size);
+                residue.resize(size);
+                faller_adder.resize(size);
+            }
+        }
+        handle_keys(
+            &mut stdin,
+            &mut controls,
+            &mut frame,
+            &mut residue,
+            &mut faller_adder,
+            &mut timestep,
+        );
+        if let Some(ref mut control_socket) = control_socket {
+            control_socket.poll(&mut faller_adder, &mut timestep);
+        }
+        if config_watcher.as_mut().is_some_and(ConfigWatcher::changed) {
+            // invalid edit should not stop the program, it is reported and the current config is kept
+            let reloaded = Config::load(cli.config_file.as_deref(), &cli).and_then(|config| {
+                reload_config(
+                    config,
+                    &mut controls,
+                    &mut frame,
+                    &mut residue,
+                    &mut faller_adder,
+                    &mut timestep,
+                )
+            });
+            match reloaded {
+                Ok(()) => controls.show_notice("config reloaded", false),
+                Err(error) => controls.show_notice(format!("config not reloaded: {error}"), true),
+            }
+        }
+        main_loop(
+            &mut faller_adder,
+            Rc::clone(&rng),
+            &mut frame,
+            &mut residue,
+            backend.as_mut(),
+            &mut timestep,
+            &controls,
+        )?;
+    }
+    Ok(())
+}
+
+/// Use `config` reloaded from file: colors, chars to use, number of fallers, message, background, key bindings,
+/// decay, frames per second and time scale change while the program runs
+///
+/// Time scale changed with keys is kept unless the file changes it.
+fn reload_config(
+    mut config: Config,
+    controls: &mut Controls,
+    frame: &mut FrameBuffer,
+    residue: &mut Residue,
+    faller_adder: &mut FallerAdder,
+    timestep: &mut Timestep,
+) -> Result<(), Error> {
+    controls.reload(&mut config)?;
+    frame.set_background(config.colors().background.clone());
+    residue.reconfigure(*config.decay(), config.chars_to_use(), *config.fps());
+    timestep.set_step(config.step_duration());
+    if config.time_scale() != faller_adder.config.borrow().time_scale() {
+        timestep.set_time_scale(*config.time_scale());
+    }
+    faller_adder.reload(config);
+    Ok(())
+}
+
+/// Print names of built-in and user themes with their preview
+fn list_themes(cli: &Cli) -> Result<(), Error> {
+    let depth = cli.color_depth.unwrap_or_else(ColorDepth::detect);
+    println!("Built-in themes:");
+    for name in BUILTIN_THEMES {
+        let theme = Theme::load(name, None)?;
+        println!("  {name:<12} {}", theme.preview(depth));
+    }
+    if let Some(dir) = cli.themes_dir.clone().or_else(Theme::default_dir) {
+        let names = Theme::user_themes(&dir);
+        if !names.is_empty() {
+            println!("User themes ({}):", dir.display());
+            for name in names {
+                match Theme::load(&name, Some(&dir)) {
+                    Ok(theme) => println!("  {name:<12} {}", theme.preview(depth)),
+                    Err(error) => println!("  {name:<12} {error}"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Serialize `config` to YAML
+fn serialize_config(config: &Config) -> Result<String, Error> {
+    serde_yaml::to_string(config)
+        .map_err(|e| Error::ConfigParse(format!("cannot serialize current config: {e}")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::MemoryBackend;
+    use crate::residue::Decay;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    /// Run `frames` frames of simulation with `seed` and return what ended up on the screen
+    fn simulate(seed: u64, frames: usize) -> String {
+        let size = Position::new(40, 12);
+        let mut config = Config::default();
+        config.set_screen_size(size);
+        let rng = new_rng(Some(seed));
+        let mut faller_adder = FallerAdder::new(Rc::new(RefCell::new(config)), Rc::clone(&rng));
+        let mut frame = FrameBuffer::new(size);
+        let mut residue = Residue::new(size, Decay::default(), "", 30);
+        let mut backend = MemoryBackend::new(size);
+        for _ in 0..frames {
+            render_frame(
+                &mut faller_adder.falling_chars.borrow_mut(),
+                &mut *rng.borrow_mut(),
+                &mut frame,
+                &mut residue,
+                &mut backend,
+            )
+            .unwrap();
+            faller_adder.add_and_retire().unwrap();
+        }
+        backend.text()
+    }
+
+    #[test]
+    fn same_seed_same_output() {
+        let output = simulate(1234, 60);
+        assert!(output.chars().any(|ch| ch != ' ' && ch != '\n'));
+        assert_eq!(output, simulate(1234, 60));
+        assert_ne!(output, simulate(4321, 60));
+    }
+
+    /// Program state changed by [reload_config]
+    struct Running {
+        controls: Controls,
+        frame: FrameBuffer,
+        residue: Residue,
+        faller_adder: FallerAdder,
+        timestep: Timestep,
+    }
+
+    impl Running {
+        fn new() -> Self {
+            let config = Config::default();
+            let size = *config.screen_size();
+            Self {
+                controls: Controls::new(&config).unwrap(),
+                frame: FrameBuffer::new(size),
+                residue: Residue::new(size, *config.decay(), config.chars_to_use(), *config.fps()),
+                timestep: Timestep::new(config.step_duration(), *config.time_scale()),
+                faller_adder: FallerAdder::new(Rc::new(RefCell::new(config)), new_rng(Some(1))),
+            }
+        }
+
+        /// Reload config file with `yaml` content the same way as the main loop does
+        fn reload(&mut self, yaml: &str) -> Result<(), Error> {
+            static RELOADS: AtomicUsize = AtomicUsize::new(0);
+            let reload = RELOADS.fetch_add(1, Ordering::SeqCst);
+            let name = format!("rumatrix-reload-{}-{reload}.yaml", std::process::id());
+            let path = std::env::temp_dir().join(name);
+            std::fs::write(&path, yaml).unwrap();
+            let path = path.to_str().unwrap().to_owned();
+            let cli = Cli::parse_from(["rumatrix", "--config-file", &path]);
+            let reloaded = Config::load(cli.config_file.as_deref(), &cli).and_then(|config| {
+                reload_config(
+                    config,
+                    &mut self.controls,
+                    &mut self.frame,
+                    &mut self.residue,
+                    &mut self.faller_adder,
+                    &mut self.timestep,
+                )
+            });
+            std::fs::remove_file(&path).unwrap();
+            reloaded
+        }
+    }
+
+    #[test]
+    fn reload_changes_fps_and_time_scale() {
+        let mut running = Running::new();
+        running.timestep.set_time_scale(0.5);
+        running.reload("chars_to_use: xyz\nfps: 50\n").unwrap();
+        assert_eq!(running.timestep.until_next(), Duration::from_millis(20));
+        // time scale changed with keys is kept when file does not change it
+        assert_eq!(running.timestep.time_scale(), 0.5);
+        running.reload("fps: 50\ntime_scale: 2.0\n").unwrap();
+        assert_eq!(running.timestep.time_scale(), 2.0);
+    }
+
+    #[test]
+    fn reload_with_empty_chars_keeps_old_config() {
+        let mut running = Running::new();
+        running.reload("chars_to_use: abc\n").unwrap();
+        assert!(matches!(running.reload("chars_to_use: ''\n"), Err(Error::ConfigParse(_))));
+        assert_eq!(running.faller_adder.config.borrow().chars_to_use(), "abc");
+        running.faller_adder.probability_to_add = 1.0;
+        running.faller_adder.add_and_retire().unwrap();
     }
 }
@@ -1,5 +1,9 @@
 use rumatrix::*;
+use std::process;
 
 fn main() {
-    program_main();
+    if let Err(error) = program_main() {
+        eprintln!("ruMatrix: {error}");
+        process::exit(error.exit_code());
+    }
 }
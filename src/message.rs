@@ -1,9 +1,10 @@
 use chrono::Local;
 use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
 
 use crate::message::TextType::StaticString;
 use crate::position::{CenteredPosition, PositionTrait, PositionType};
-use crate::{colors::Color, Position};
+use crate::{colors::Color, error::Error, unicode, Position};
 
 /// Struct holds message currently displayed on screen with its:
 /// `position` and `text`
@@ -17,49 +18,55 @@ pub struct Message {
     pub text: TextType,
     /// [Color] of message
     pub color: Color,
+    /// Optional background [Color] of message, e.g. to highlight it, screen background is used if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<Color>,
 }
 
 impl Message {
     /// Returns centered message wrapped in [Some] or [None] if not possible to center
     pub fn new_centered_or_none(bounds: Position, text: TextType, color: Color) -> Option<Self> {
-        let position = PositionType::Center(CenteredPosition::new(&bounds, &text));
+        let position = PositionType::Center(CenteredPosition::new(&bounds, &text).ok()?);
         Some(Message {
             position,
             text,
             color,
             bounds,
+            background: None,
         })
     }
 
-    /// Check if `other_position` is inside of message's `position`
+    /// Check if `other_position` is inside of message's `position`, wide graphemes take two columns
     pub fn is_position_inside_message(&self, other_position: &Position) -> bool {
         other_position.y() == self.position.y()
             && other_position.x() >= self.position.x()
-            && other_position.x() < self.position.x() + self.text.to_string().len() as u16
-    }
-
-    /// Get [char], use it only if `is_position_inside_message` is true.
-    /// Otherwise it might panic when calculating [char] to get
-    fn get_message_char(&self, other_position: &Position) -> char {
-        let nth = (other_position.x() - self.position.x()) as usize;
-        self.text.to_string().chars().nth(nth).unwrap()
+            && usize::from(other_position.x() - self.position.x()) < unicode::width(&self.text.to_string())
     }
 
     /// Check if `other_position` is inside of message's `position`
     ///
-    /// If true: return [Some] with char to be displayed
+    /// If true: return [Some] with grapheme covering `other_position` and [Position] where the grapheme starts,
+    /// which differs from `other_position` in second column of wide grapheme
     ///
     /// If false: return [None]
-    pub fn get_char_in_position(&self, other_position: &Position) -> Option<char> {
-        if self.is_position_inside_message(other_position) {
-            Some(self.get_message_char(other_position))
-        } else {
-            None
+    pub fn get_grapheme_in_position(&self, other_position: &Position) -> Option<(Position, String)> {
+        if other_position.y() != self.position.y() || other_position.x() < self.position.x() {
+            return None;
+        }
+        let text = self.text.to_string();
+        let mut x = self.position.x();
+        for grapheme in unicode::graphemes(&text) {
+            let width = unicode::grapheme_width(grapheme) as u16;
+            if other_position.x() < x + width {
+                return Some((Position::new(x, other_position.y()), grapheme.to_owned()));
+            }
+            x += width;
         }
+        None
     }
 
     /// Update [PositionType] inside of [Message]
-    pub fn update_position(&mut self) {
+    pub fn update_position(&mut self) -> Result<(), Error> {
         self.position.update(&self.bounds, &self.text)
     }
 }
@@ -79,14 +86,14 @@ impl Default for TextType {
     }
 }
 
-impl ToString for TextType {
-    fn to_string(&self) -> String {
+impl Display for TextType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TextType::StaticString(ref text) => text.clone(),
+            TextType::StaticString(ref text) => write!(f, "{text}"),
             TextType::CurrentDateTime(ref format) => {
                 let date = Local::now();
                 let format = date.format(format);
-                format!("{format}")
+                write!(f, "{format}")
             }
         }
     }
@@ -116,9 +123,18 @@ mod test {
             Color::Palette(1)
         );
         assert!(msg.is_some());
+
+        let bounds = Position::new(4, 5);
+        let msg = Message::new_centered_or_none(
+            bounds,
+            TextType::StaticString("Lorem".to_owned()),
+            Color::Palette(1)
+        );
+        assert!(msg.is_none());
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn is_position_inside_message() {
         let msg = get_test_msg();
 
@@ -136,29 +152,49 @@ mod test {
     }
 
     #[test]
-    fn get_char_in_position() {
+    fn get_grapheme_in_position() {
         let msg = get_test_msg();
-        
+
         let position = Position::new(2, 2);
-        let ch = msg.get_char_in_position(&position);
-        assert!(ch.is_none());
+        assert!(msg.get_grapheme_in_position(&position).is_none());
 
         let position = Position::new(6, 3);
-        let ch = msg.get_char_in_position(&position);
-        assert!(ch.is_none());
+        assert!(msg.get_grapheme_in_position(&position).is_none());
 
         let position = Position::new(7, 3);
-        let ch = msg.get_char_in_position(&position);
-        assert!(ch.is_some());
-        assert_eq!(ch.unwrap(), 'L');
+        assert_eq!(msg.get_grapheme_in_position(&position), Some((position, "L".to_owned())));
 
         let position = Position::new(11, 3);
-        let ch = msg.get_char_in_position(&position);
-        assert!(ch.is_some());
-        assert_eq!(ch.unwrap(), 'm');
+        assert_eq!(msg.get_grapheme_in_position(&position), Some((position, "m".to_owned())));
 
         let position = Position::new(12, 3);
-        let ch = msg.get_char_in_position(&position);
-        assert!(ch.is_none());
+        assert!(msg.get_grapheme_in_position(&position).is_none());
+    }
+
+    #[test]
+    fn wide_and_combining_graphemes() {
+        let bounds = Position::new(20, 6);
+        // 2 + 2 + 1 columns
+        let msg = Message::new_centered_or_none(
+            bounds,
+            TextType::StaticString("マトe\u{301}".to_owned()),
+            Color::Palette(1),
+        )
+        .unwrap();
+        assert_eq!(msg.position.x(), 7);
+        assert_eq!(
+            msg.get_grapheme_in_position(&Position::new(8, 3)),
+            Some((Position::new(7, 3), "マ".to_owned()))
+        );
+        assert_eq!(
+            msg.get_grapheme_in_position(&Position::new(9, 3)),
+            Some((Position::new(9, 3), "ト".to_owned()))
+        );
+        assert_eq!(
+            msg.get_grapheme_in_position(&Position::new(11, 3)),
+            Some((Position::new(11, 3), "e\u{301}".to_owned()))
+        );
+        assert!(msg.is_position_inside_message(&Position::new(11, 3)));
+        assert!(!msg.is_position_inside_message(&Position::new(12, 3)));
     }
 }
@@ -0,0 +1,119 @@
+/// Color in [OKLab](https://bottosson.github.io/posts/oklab/) perceptual color space
+///
+/// Equal changes of `lightness` look like equal changes of brightness, regardless of hue
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Oklab {
+    /// Perceived lightness, 0.0 is black and 1.0 is white
+    pub lightness: f32,
+    /// Green (negative) to red (positive) axis
+    pub a: f32,
+    /// Blue (negative) to yellow (positive) axis
+    pub b: f32,
+}
+
+/// Convert sRGB component (0-255) to linear light (0.0 - 1.0)
+fn to_linear(component: u8) -> f32 {
+    let c = f32::from(component) / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert linear light (0.0 - 1.0) to sRGB component (0-255)
+fn from_linear(c: f32) -> u8 {
+    let c = if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+impl Oklab {
+    /// Convert sRGB color to [Oklab]
+    pub fn from_rgb((r, g, b): (u8, u8, u8)) -> Self {
+        let (r, g, b) = (to_linear(r), to_linear(g), to_linear(b));
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        Self {
+            lightness: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    /// Convert to linear RGB, components are outside of 0.0 - 1.0 if color cannot be displayed
+    fn to_linear_rgb(self) -> (f32, f32, f32) {
+        let l = (self.lightness + 0.396_337_78 * self.a + 0.215_803_76 * self.b).powi(3);
+        let m = (self.lightness - 0.105_561_346 * self.a - 0.063_854_17 * self.b).powi(3);
+        let s = (self.lightness - 0.089_484_18 * self.a - 1.291_485_5 * self.b).powi(3);
+        (
+            4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+            -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+            -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+        )
+    }
+
+    /// Convert to sRGB color
+    ///
+    /// Colors which cannot be displayed lose some of their saturation (keeping lightness and hue) until they can be
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        let in_gamut = |(r, g, b): (f32, f32, f32)| {
+            const EPSILON: f32 = 0.0001;
+            [r, g, b]
+                .iter()
+                .all(|c| (-EPSILON..=1.0 + EPSILON).contains(c))
+        };
+        let with_saturation = |scale: f32| Oklab {
+            a: self.a * scale,
+            b: self.b * scale,
+            ..self
+        };
+        let mut rgb = self.to_linear_rgb();
+        if !in_gamut(rgb) {
+            // binary search for the highest saturation which can be displayed
+            let (mut low, mut high) = (0.0, 1.0);
+            for _ in 0..20 {
+                let middle = (low + high) / 2.0;
+                if in_gamut(with_saturation(middle).to_linear_rgb()) {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            rgb = with_saturation(low).to_linear_rgb();
+        }
+        (from_linear(rgb.0), from_linear(rgb.1), from_linear(rgb.2))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for rgb in [(0, 0, 0), (255, 255, 255), (127, 222, 90), (255, 0, 0), (13, 89, 30)] {
+            assert_eq!(Oklab::from_rgb(rgb).to_rgb(), rgb);
+        }
+    }
+
+    #[test]
+    fn lightness() {
+        assert!(Oklab::from_rgb((0, 0, 0)).lightness.abs() < 0.001);
+        assert!((Oklab::from_rgb((255, 255, 255)).lightness - 1.0).abs() < 0.001);
+        // yellow looks much lighter than blue
+        assert!(Oklab::from_rgb((255, 255, 0)).lightness > Oklab::from_rgb((0, 0, 255)).lightness + 0.4);
+    }
+
+    #[test]
+    fn out_of_gamut_keeps_hue() {
+        let mut blue = Oklab::from_rgb((0, 0, 255));
+        blue.lightness = 0.7;
+        let (r, g, b) = blue.to_rgb();
+        assert!(b > r && b > g, "{:?}", (r, g, b));
+    }
+}
@@ -1,4 +1,6 @@
+use crate::error::Error;
 use crate::message::TextType;
+use crate::unicode;
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
 
@@ -18,13 +20,14 @@ pub trait PositionTrait {
         self.y() > bounds.y() || self.x() > bounds.x()
     }
     /// Update the position based on text
-    fn update(&mut self, _bounds: &Position, _text: &TextType) {
+    fn update(&mut self, _bounds: &Position, _text: &TextType) -> Result<(), Error> {
         // do nothing by default
+        Ok(())
     }
 }
 
 /// Basic structure to hold position on the screen
-#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, Debug)]
 pub struct Position {
     /// x coordinate
     x: u16,
@@ -69,15 +72,15 @@ pub struct CenteredPosition {
 }
 
 impl CenteredPosition {
-    /// New [CenteredPosition]
-    pub fn new(bounds: &Position, text: &TextType) -> Self {
+    /// New [CenteredPosition], returns [Error::MessageTooLong] if `text` does not fit in `bounds`
+    pub fn new(bounds: &Position, text: &TextType) -> Result<Self, Error> {
         let mut new_centered = Self {
             position: Position::default(),
             last_text: TextType::StaticString("".into()),
             last_bounds: Position::default(),
         };
-        new_centered.update(bounds, text);
-        new_centered
+        new_centered.update(bounds, text)?;
+        Ok(new_centered)
     }
 }
 
@@ -98,20 +101,24 @@ impl PositionTrait for CenteredPosition {
         self.position.set_y(y);
     }
 
-    fn update(&mut self, bounds: &Position, text: &TextType) {
+    fn update(&mut self, bounds: &Position, text: &TextType) -> Result<(), Error> {
         // Update only if necessary
         if *text != self.last_text || self.last_bounds != *bounds {
-            if bounds.x < text.to_string().len() as u16 {
-                // TO DO: change return type to result and return error here
-                panic!("Message provided is longer than screen");
+            let length = unicode::width(&text.to_string());
+            if usize::from(bounds.x) < length {
+                return Err(Error::MessageTooLong {
+                    length,
+                    width: bounds.x,
+                });
             }
-            let x = (bounds.x - text.to_string().len() as u16) / 2;
+            let x = (bounds.x - length as u16) / 2;
             let y = bounds.y / 2;
             self.position.x = x;
             self.position.y = y;
             self.last_text = text.clone();
             self.last_bounds = *bounds;
         }
+        Ok(())
     }
 }
 
@@ -155,6 +162,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn position_is_out_of_bounds() {
         let bounds = Position::new(30, 30);
         let position = Position::new(15, 15);
@@ -169,7 +177,7 @@ mod test {
     fn centered_position_with_static_text() {
         let bounds = Position::new(30, 30);
         let text = TextType::StaticString("X".to_owned());
-        let position = CenteredPosition::new(&bounds, &text);
+        let position = CenteredPosition::new(&bounds, &text).unwrap();
         assert_eq!(position.x(), 14);
         assert_eq!(position.y(), 15);
     }
@@ -178,11 +186,24 @@ mod test {
     fn centered_position_with_static_text_updating() {
         let bounds = Position::new(30, 30);
         let text = TextType::StaticString("X".to_owned());
-        let mut position = CenteredPosition::new(&bounds, &text);
+        let mut position = CenteredPosition::new(&bounds, &text).unwrap();
         let bounds = Position::new(31, 22);
         let text = TextType::StaticString("Lorem Ipsum".to_owned());
-        position.update(&bounds, &text);
+        position.update(&bounds, &text).unwrap();
         assert_eq!(position.x(), 10);
         assert_eq!(position.y(), 11);
     }
+
+    #[test]
+    fn centered_position_with_too_long_text() {
+        let bounds = Position::new(4, 30);
+        let text = TextType::StaticString("Lorem".to_owned());
+        assert!(matches!(
+            CenteredPosition::new(&bounds, &text),
+            Err(Error::MessageTooLong {
+                length: 5,
+                width: 4
+            })
+        ));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,174 @@
+use crate::position::{Position, PositionTrait};
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of timestamps for recorded events
+pub enum RecordClock {
+    /// Real time elapsed since recording started, used with live terminal
+    RealTime(Instant),
+    /// Each flush is one frame of fixed duration, used in headless mode where frames are not rendered in real time
+    Frames {
+        /// Duration of single frame
+        frame_duration: Duration,
+        /// Number of frames recorded so far
+        frames: u32,
+    },
+}
+
+impl RecordClock {
+    /// [RecordClock::RealTime] starting now
+    pub fn real_time() -> Self {
+        RecordClock::RealTime(Instant::now())
+    }
+
+    /// [RecordClock::Frames] with `frame_duration`
+    pub fn frames(frame_duration: Duration) -> Self {
+        RecordClock::Frames {
+            frame_duration,
+            frames: 0,
+        }
+    }
+
+    /// Timestamp of the next event in seconds since start of recording
+    fn tick(&mut self) -> f64 {
+        match self {
+            RecordClock::RealTime(start) => start.elapsed().as_secs_f64(),
+            RecordClock::Frames {
+                frame_duration,
+                frames,
+            } => {
+                let time = (*frame_duration * *frames).as_secs_f64();
+                *frames += 1;
+                time
+            }
+        }
+    }
+}
+
+/// Writes asciinema's [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) file
+pub struct AsciicastWriter<W: Write> {
+    /// Where recording is written
+    out: W,
+}
+
+impl AsciicastWriter<BufWriter<File>> {
+    /// Create asciicast file in `path` for terminal of `size`
+    pub fn create(path: &str, size: Position) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), size)
+    }
+}
+
+impl<W: Write> AsciicastWriter<W> {
+    /// Create new [AsciicastWriter] writing header for terminal of `size` to `out`
+    pub fn new(mut out: W, size: Position) -> io::Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let header = json!({
+            "version": 2,
+            "width": size.x(),
+            "height": size.y(),
+            "timestamp": timestamp,
+            "env": {
+                "TERM": std::env::var("TERM").unwrap_or_default(),
+            },
+        });
+        writeln!(out, "{header}")?;
+        Ok(Self { out })
+    }
+
+    /// Write output event with `data` at `time` seconds since start of recording
+    pub fn output(&mut self, time: f64, data: &[u8]) -> io::Result<()> {
+        let event = json!([time, "o", String::from_utf8_lossy(data)]);
+        writeln!(self.out, "{event}")
+    }
+
+    /// Get reference to underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+}
+
+/// Writer which passes everything to inner writer and records it using [AsciicastWriter]
+///
+/// Bytes written between flushes are recorded as one event, so with [TermionBackend] each frame is one event
+pub struct Recorder<W: Write, C: Write> {
+    /// Inner writer, e.g. terminal
+    inner: W,
+    /// Recording
+    cast: AsciicastWriter<C>,
+    /// Source of event timestamps
+    clock: RecordClock,
+    /// Bytes written since last flush
+    pending: Vec<u8>,
+}
+
+impl<W: Write, C: Write> Recorder<W, C> {
+    /// Create new [Recorder] passing bytes to `inner`
+    pub fn new(inner: W, cast: AsciicastWriter<C>, clock: RecordClock) -> Self {
+        Self {
+            inner,
+            cast,
+            clock,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Get reference to the recording
+    pub fn cast(&self) -> &AsciicastWriter<C> {
+        &self.cast
+    }
+}
+
+impl<W: Write, C: Write> Write for Recorder<W, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.pending.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if !self.pending.is_empty() {
+            let time = self.clock.tick();
+            self.cast.output(time, &self.pending)?;
+            self.pending.clear();
+        }
+        self.cast.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn header_and_events() {
+        let cast = AsciicastWriter::new(Vec::new(), Position::new(80, 24)).unwrap();
+        let mut recorder = Recorder::new(
+            Vec::new(),
+            cast,
+            RecordClock::frames(Duration::from_millis(500)),
+        );
+        write!(recorder, "a\u{1b}[2J").unwrap();
+        recorder.flush().unwrap();
+        // nothing written, no event
+        recorder.flush().unwrap();
+        write!(recorder, "b\"").unwrap();
+        recorder.flush().unwrap();
+
+        let recording = String::from_utf8(recorder.cast().get_ref().clone()).unwrap();
+        let lines: Vec<_> = recording.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header["version"], 2);
+        assert_eq!(header["width"], 80);
+        assert_eq!(header["height"], 24);
+        assert_eq!(lines[1], r#"[0.0,"o","a\u001b[2J"]"#);
+        assert_eq!(lines[2], r#"[0.5,"o","b\""]"#);
+        assert_eq!(recorder.inner, b"a\x1b[2Jb\"");
+    }
+}
@@ -0,0 +1,250 @@
+use crate::colors::Color;
+use crate::frame_buffer::{Cell, FrameBuffer};
+use crate::position::{Position, PositionTrait};
+use crate::unicode;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How characters left behind by fallers decay, see [Residue]
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Decay {
+    /// Number of seconds after which left behind character fades into the background and is erased,
+    /// 0 means it stays until the screen is cleared
+    pub duration: f32,
+    /// Probability (0.0 - 1.0) that left behind character changes into a random one in each frame
+    pub mutation: f64,
+}
+
+impl Decay {
+    /// Is there anything to do with left behind characters
+    pub fn is_enabled(&self) -> bool {
+        self.duration > 0.0 || self.mutation > 0.0
+    }
+}
+
+/// Character left behind in single cell
+struct ResidueCell {
+    /// Displayed grapheme
+    symbol: String,
+    /// [Color] when character was left behind
+    color: Color,
+    /// Number of frames since character was left behind
+    age: u32,
+}
+
+/// Layer of characters left behind by fallers, which fade towards the background and are erased after [Decay::duration]
+pub struct Residue {
+    /// Size of the layer, same as size of the [FrameBuffer]
+    size: Position,
+    /// How left behind characters decay
+    decay: Decay,
+    /// Frames per second in which [Residue::render] is called
+    fps: u32,
+    /// Number of frames after which character is erased, [Decay::duration] converted using `fps`, 0 means never
+    lifetime: u32,
+    /// Graphemes used when character mutates
+    symbols: Vec<String>,
+    /// Left behind characters, row by row
+    cells: Vec<Option<ResidueCell>>,
+}
+
+impl Residue {
+    /// Create new empty [Residue] of `size` rendered `fps` times per second, mutated characters are picked
+    /// from `chars_to_use`
+    pub fn new(size: Position, decay: Decay, chars_to_use: &str, fps: u32) -> Self {
+        let mut cells = Vec::new();
+        cells.resize_with(usize::from(size.x()) * usize::from(size.y()), || None);
+        Self {
+            size,
+            decay,
+            fps,
+            lifetime: Self::lifetime(decay, fps),
+            symbols: unicode::graphemes(chars_to_use).into_iter().map(String::from).collect(),
+            cells,
+        }
+    }
+
+    /// Number of frames at `fps` after which character is erased, at least one if `decay` has duration
+    fn lifetime(decay: Decay, fps: u32) -> u32 {
+        if decay.duration > 0.0 {
+            ((decay.duration * fps as f32).round() as u32).max(1)
+        } else {
+            0
+        }
+    }
+
+    /// Resize layer to new `size`, all characters are forgotten
+    pub fn resize(&mut self, size: Position) {
+        *self = Self::new(size, self.decay, &self.symbols.concat(), self.fps);
+    }
+
+    /// Use `decay`, graphemes of `chars_to_use` and `fps` from now on, e.g. after config was reloaded
+    ///
+    /// Characters already left behind keep their age and continue decaying with the new `decay`
+    pub fn reconfigure(&mut self, decay: Decay, chars_to_use: &str, fps: u32) {
+        self.decay = decay;
+        self.fps = fps;
+        self.lifetime = Self::lifetime(decay, fps);
+        self.symbols = unicode::graphemes(chars_to_use).into_iter().map(String::from).collect();
+    }
+
+    /// Forget all characters, e.g. after screen was cleared
+    pub fn clear(&mut self) {
+        self.cells.fill_with(|| None);
+    }
+
+    /// Index of `position` in cells or [None] if `position` is outside of the layer
+    fn index(&self, position: &Position) -> Option<usize> {
+        if position.x() == 0 || position.y() == 0 || position.is_out_of_bounds(&self.size) {
+            return None;
+        }
+        Some(usize::from(position.y() - 1) * usize::from(self.size.x()) + usize::from(position.x() - 1))
+    }
+
+    /// Start decaying `cell` left behind in `position`, cells without color or covered by wide symbol are ignored
+    pub fn leave(&mut self, position: &Position, cell: &Cell) {
+        if !self.decay.is_enabled() || cell.is_continuation() {
+            return;
+        }
+        if let (Some(index), Some(color)) = (self.index(position), cell.style.color.clone()) {
+            self.cells[index] = Some(ResidueCell {
+                symbol: cell.symbol.clone(),
+                color,
+                age: 0,
+            });
+        }
+    }
+
+    /// Age all characters by one frame and draw them into `frame`, fully faded ones are erased
+    pub fn render(&mut self, rng: &mut dyn RngCore, frame: &mut FrameBuffer) {
+        if !self.decay.is_enabled() {
+            return;
+        }
+        let background = frame.background().map_or((0, 0, 0), Color::to_rgb);
+        let width = usize::from(self.size.x());
+        for (index, slot) in self.cells.iter_mut().enumerate() {
+            let Some(cell) = slot else {
+                continue;
+            };
+            let position = Position::new((index % width) as u16 + 1, (index / width) as u16 + 1);
+            cell.age = cell.age.saturating_add(1);
+            if self.lifetime > 0 && cell.age >= self.lifetime {
+                frame.put(&position, Cell::blank(None));
+                *slot = None;
+                continue;
+            }
+            if self.decay.mutation > 0.0 && rng.gen_bool(self.decay.mutation) {
+                if let Some(symbol) = self.symbols.choose(rng) {
+                    cell.symbol.clone_from(symbol);
+                }
+            }
+            let color = match self.lifetime {
+                0 => cell.color.clone(),
+                lifetime => fade(&cell.color, background, cell.age as f32 / lifetime as f32),
+            };
+            frame.put(&position, Cell::new(cell.symbol.as_str(), color));
+        }
+    }
+}
+
+/// [Color] `t` (0.0 - 1.0) of the way from `color` to `background`
+fn fade(color: &Color, background: (u8, u8, u8), t: f32) -> Color {
+    let from = color.to_rgb();
+    let mix = |from: u8, to: u8| (f32::from(from) + (f32::from(to) - f32::from(from)) * t).round() as u8;
+    Color::RGB {
+        r: mix(from.0, background.0),
+        g: mix(from.1, background.1),
+        b: mix(from.2, background.2),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+
+    #[test]
+    fn fades_and_erases() {
+        let size = Position::new(3, 3);
+        let mut frame = FrameBuffer::new(size);
+        let decay = Decay {
+            duration: 0.5,
+            mutation: 0.0,
+        };
+        let mut residue = Residue::new(size, decay, "ab", 8);
+        let position = Position::new(2, 2);
+        residue.leave(&position, &Cell::new('x', Color::RGB { r: 200, g: 100, b: 0 }));
+        let mut rng = StepRng::new(0, 1);
+        residue.render(&mut rng, &mut frame);
+        assert_eq!(
+            frame.get(&position),
+            Some(&Cell::new('x', Color::RGB { r: 150, g: 75, b: 0 }))
+        );
+        residue.render(&mut rng, &mut frame);
+        residue.render(&mut rng, &mut frame);
+        assert_eq!(
+            frame.get(&position),
+            Some(&Cell::new('x', Color::RGB { r: 50, g: 25, b: 0 }))
+        );
+        residue.render(&mut rng, &mut frame);
+        assert_eq!(frame.get(&position), Some(&Cell::default()));
+    }
+
+    #[test]
+    fn fades_towards_background() {
+        let size = Position::new(1, 1);
+        let mut frame = FrameBuffer::new(size);
+        frame.set_background(Some(Color::RGB { r: 0, g: 0, b: 100 }));
+        let decay = Decay {
+            duration: 2.0,
+            mutation: 0.0,
+        };
+        let mut residue = Residue::new(size, decay, "ab", 1);
+        residue.leave(&Position::new(1, 1), &Cell::new('x', Color::RGB { r: 200, g: 0, b: 0 }));
+        residue.render(&mut StepRng::new(0, 1), &mut frame);
+        assert_eq!(
+            frame.get(&Position::new(1, 1)).unwrap().style.color,
+            Some(Color::RGB { r: 100, g: 0, b: 50 })
+        );
+    }
+
+    #[test]
+    fn mutates_glyph() {
+        let size = Position::new(1, 1);
+        let mut frame = FrameBuffer::new(size);
+        let decay = Decay {
+            duration: 0.0,
+            mutation: 1.0,
+        };
+        let mut residue = Residue::new(size, decay, "m", 30);
+        residue.leave(&Position::new(1, 1), &Cell::new('x', Color::Palette(2)));
+        residue.render(&mut StepRng::new(0, 1), &mut frame);
+        assert_eq!(frame.get(&Position::new(1, 1)), Some(&Cell::new('m', Color::Palette(2))));
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let size = Position::new(1, 1);
+        let mut frame = FrameBuffer::new(size);
+        let mut residue = Residue::new(size, Decay::default(), "ab", 30);
+        residue.leave(&Position::new(1, 1), &Cell::new('x', Color::Palette(2)));
+        residue.render(&mut StepRng::new(0, 1), &mut frame);
+        assert_eq!(frame.get(&Position::new(1, 1)), Some(&Cell::default()));
+    }
+
+    #[test]
+    fn duration_does_not_depend_on_fps() {
+        let decay = Decay {
+            duration: 2.0,
+            mutation: 0.0,
+        };
+        assert_eq!(Residue::new(Position::new(1, 1), decay, "ab", 10).lifetime, 20);
+        assert_eq!(Residue::new(Position::new(1, 1), decay, "ab", 60).lifetime, 120);
+        let short = Decay {
+            duration: 0.001,
+            mutation: 0.0,
+        };
+        assert_eq!(Residue::new(Position::new(1, 1), short, "ab", 10).lifetime, 1);
+    }
+}
@@ -0,0 +1,63 @@
+use rand::prelude::*;
+use std::{cell::RefCell, rc::Rc};
+
+/// Create random number generator used by the whole program
+///
+/// With `seed` every run produces the same sequence of numbers, otherwise generator is seeded from entropy
+pub fn new_rng(seed: Option<u64>) -> Rc<RefCell<dyn RngCore>> {
+    match seed {
+        Some(seed) => Rc::new(RefCell::new(StdRng::seed_from_u64(seed))),
+        None => Rc::new(RefCell::new(StdRng::from_entropy())),
+    }
+}
+
+/// [RngCore] implementation which draws numbers from shared generator created by [new_rng]
+///
+/// Useful where owned [RngCore] is needed e.g. [RandomVecBag::with_custom_rng]
+pub struct SharedRng(pub Rc<RefCell<dyn RngCore>>);
+
+impl RngCore for SharedRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.borrow_mut().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.borrow_mut().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.borrow_mut().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.borrow_mut().try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let first = new_rng(Some(42));
+        let second = new_rng(Some(42));
+        let first: Vec<u32> = (0..8).map(|_| first.borrow_mut().next_u32()).collect();
+        let second: Vec<u32> = (0..8).map(|_| second.borrow_mut().next_u32()).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn shared_rng_draws_from_shared_generator() {
+        let rng = new_rng(Some(7));
+        let mut shared = SharedRng(Rc::clone(&rng));
+        let expected: Vec<u32> = {
+            let other = new_rng(Some(7));
+            let mut other = other.borrow_mut();
+            (0..4).map(|_| other.next_u32()).collect()
+        };
+        assert_eq!(shared.next_u32(), expected[0]);
+        assert_eq!(rng.borrow_mut().next_u32(), expected[1]);
+        assert_eq!(shared.next_u32(), expected[2]);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::charset;
+use crate::colors::{Color, ColorDepth, Colors};
+use crate::error::Error;
+use crate::frame_buffer::Style;
+use crate::gradient::{Easing, Gradient, GradientStop};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use termion::style;
+
+/// Names of built-in themes, see [Theme::builtin]
+pub const BUILTIN_THEMES: [&str; 6] = ["classic", "amber", "ice", "blood", "synthwave", "monochrome"];
+
+/// Named preset of [Colors], message color and chars used by fallers
+///
+/// Besides built-in themes, themes are loaded from `<name>.yaml` files in themes directory
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct Theme {
+    /// [Colors] of fallers
+    pub colors: Colors,
+    /// [Color] of the message, message keeps its color if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_color: Option<Color>,
+    /// Chars used by fallers, configured chars are kept if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chars_to_use: Option<String>,
+}
+
+/// Shorter way to write [Color::RGB]
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::RGB { r, g, b }
+}
+
+impl Theme {
+    /// Theme with three flat colors using built-in `charset`, see [charset::builtin]
+    fn flat(trail: Color, head: Color, left_behind: Color, message_color: Color, charset: &str) -> Self {
+        Self {
+            colors: Colors {
+                trail,
+                head,
+                left_behind,
+                gradient: None,
+                background: None,
+            },
+            message_color: Some(message_color),
+            chars_to_use: charset::builtin(charset),
+        }
+    }
+
+    /// Get built-in theme with `name`, see [BUILTIN_THEMES]
+    pub fn builtin(name: &str) -> Option<Self> {
+        let theme = match name {
+            "classic" => Self::flat(
+                rgb(127, 222, 90),
+                rgb(100, 150, 110),
+                rgb(13, 89, 30),
+                rgb(41, 194, 148),
+                "katakana",
+            ),
+            "amber" => Self::flat(
+                rgb(255, 176, 0),
+                rgb(255, 226, 140),
+                rgb(122, 72, 0),
+                rgb(255, 204, 80),
+                "hex",
+            ),
+            "ice" => Self::flat(
+                rgb(120, 200, 255),
+                rgb(230, 250, 255),
+                rgb(20, 70, 130),
+                rgb(180, 230, 255),
+                "braille",
+            ),
+            "blood" => Self::flat(
+                rgb(200, 0, 0),
+                rgb(255, 110, 110),
+                rgb(90, 0, 0),
+                rgb(255, 60, 60),
+                "runic",
+            ),
+            "synthwave" => Self {
+                colors: Colors {
+                    trail: rgb(255, 60, 200),
+                    head: rgb(90, 240, 255),
+                    left_behind: rgb(80, 20, 120),
+                    gradient: Some(Gradient {
+                        stops: vec![
+                            GradientStop {
+                                position: 0,
+                                color: rgb(90, 240, 255),
+                            },
+                            GradientStop {
+                                position: 50,
+                                color: rgb(255, 60, 200),
+                            },
+                            GradientStop {
+                                position: 100,
+                                color: rgb(120, 30, 160),
+                            },
+                        ],
+                        easing: Easing::Linear,
+                    }),
+                    background: None,
+                },
+                message_color: Some(rgb(255, 200, 60)),
+                chars_to_use: charset::builtin("greek"),
+            },
+            "monochrome" => Self::flat(
+                rgb(190, 190, 190),
+                rgb(255, 255, 255),
+                rgb(80, 80, 80),
+                rgb(255, 255, 255),
+                "binary",
+            ),
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// Default themes directory: `$XDG_CONFIG_HOME/rumatrix/themes` or `~/.config/rumatrix/themes`
+    pub fn default_dir() -> Option<PathBuf> {
+        let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(config_dir.join("rumatrix").join("themes"))
+    }
+
+    /// Load theme with `name`, from `<name>.yaml` in `themes_dir` if it exists, built-in theme otherwise
+    pub fn load(name: &str, themes_dir: Option<&Path>) -> Result<Self, Error> {
+        if let Some(path) = themes_dir.map(|dir| dir.join(format!("{name}.yaml"))) {
+            if path.is_file() {
+                let theme = fs::read_to_string(&path).map_err(|e| {
+                    Error::ConfigParse(format!("cannot read theme '{}': {e}", path.display()))
+                })?;
+                let mut theme: Theme = serde_yaml::from_str(&theme).map_err(|e| {
+                    Error::ConfigParse(format!("incorrect theme '{}': {e}", path.display()))
+                })?;
+                if let Some(ref mut gradient) = theme.colors.gradient {
+                    gradient.validate()?;
+                }
+                return Ok(theme);
+            }
+        }
+        Self::builtin(name).ok_or_else(|| {
+            Error::ConfigParse(format!(
+                "unknown theme '{name}', use --list-themes to see available ones"
+            ))
+        })
+    }
+
+    /// Names of themes in `themes_dir`, sorted
+    pub fn user_themes(themes_dir: &Path) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(themes_dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "yaml" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Single line preview of the theme: head, trail and character left behind followed by message color
+    pub fn preview(&self, depth: ColorDepth) -> String {
+        const TRAIL_SIZE: usize = 12;
+        let ansi = |color: &Color, bold: bool| {
+            Style {
+                color: Some(color.clone()),
+                bold,
+                background: self.colors.background.clone(),
+            }
+            .downgrade(depth)
+            .get_ansi_string()
+        };
+        let mut chars = self
+            .chars_to_use
+            .as_deref()
+            .unwrap_or("ruMatrix")
+            .chars()
+            .cycle();
+        let mut preview = ansi(&self.colors.head, true);
+        preview.extend(chars.next());
+        for i in 0..TRAIL_SIZE {
+            let color = match self.colors.gradient {
+                Some(ref gradient) => gradient.color_at(i as f32 / TRAIL_SIZE as f32),
+                None => self.colors.trail.clone(),
+            };
+            preview.push_str(&ansi(&color, false));
+            preview.extend(chars.next());
+        }
+        preview.push_str(&ansi(&self.colors.left_behind, false));
+        preview.extend(chars.next());
+        if let Some(ref color) = self.message_color {
+            preview.push_str(&ansi(color, false));
+            preview.push_str("  message");
+        }
+        preview.push_str(style::Reset.as_ref());
+        preview
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builtin_themes_exist() {
+        for name in BUILTIN_THEMES {
+            let theme = Theme::builtin(name).unwrap();
+            assert!(theme.chars_to_use.is_some_and(|chars| !chars.is_empty()), "{name}");
+        }
+        assert!(Theme::builtin("unknown").is_none());
+    }
+
+    #[test]
+    fn load_user_theme() {
+        let dir = env::temp_dir().join(format!("rumatrix-themes-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut theme = Theme::builtin("ice").unwrap();
+        theme.chars_to_use = Some("01".into());
+        fs::write(dir.join("binary.yaml"), serde_yaml::to_string(&theme).unwrap()).unwrap();
+        // user theme hides built-in one with the same name
+        fs::write(dir.join("amber.yaml"), serde_yaml::to_string(&theme).unwrap()).unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        let mut broken = Theme::builtin("synthwave").unwrap();
+        broken.colors.gradient.as_mut().unwrap().stops[0].position = 150;
+        fs::write(dir.join("broken.yaml"), serde_yaml::to_string(&broken).unwrap()).unwrap();
+
+        assert_eq!(Theme::user_themes(&dir), ["amber", "binary", "broken"]);
+        assert!(matches!(Theme::load("broken", Some(&dir)), Err(Error::InvalidColor(_))));
+        assert_eq!(Theme::load("binary", Some(&dir)).unwrap(), theme);
+        assert_eq!(Theme::load("amber", Some(&dir)).unwrap(), theme);
+        assert_eq!(
+            Theme::load("blood", Some(&dir)).unwrap(),
+            Theme::builtin("blood").unwrap()
+        );
+        assert!(Theme::load("unknown", Some(&dir)).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::{Duration, Instant};
+
+/// Maximum number of simulation steps done before the next frame is rendered
+///
+/// When rendering is slower than that (e.g. over slow SSH link), the rest of the elapsed time is dropped
+/// and the animation slows down instead of never catching up
+pub const MAX_CATCH_UP_STEPS: u32 = 8;
+
+/// Fixed timestep clock, decides how many simulation steps are due so that simulation keeps up with real time
+/// regardless of how long rendering takes
+pub struct Timestep {
+    /// Simulated time of one step
+    step: Duration,
+    /// How fast simulated time passes compared to real time, e.g. 0.5 for slow motion
+    time_scale: f64,
+    /// Simulated time which passed, but was not simulated yet
+    accumulator: Duration,
+    /// When [Timestep::tick] was called last time
+    last_tick: Instant,
+    /// Time does not pass, only requested single steps are done
+    paused: bool,
+    /// Single steps requested while paused
+    pending_steps: u32,
+}
+
+impl Timestep {
+    /// Create new [Timestep] doing steps of `step` duration, simulated time passes `time_scale` times as fast as real time
+    pub fn new(step: Duration, time_scale: f32) -> Self {
+        Self {
+            step,
+            time_scale: f64::from(time_scale),
+            accumulator: Duration::ZERO,
+            last_tick: Instant::now(),
+            paused: false,
+            pending_steps: 0,
+        }
+    }
+
+    /// How fast simulated time passes compared to real time
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale as f32
+    }
+
+    /// Change how fast simulated time passes compared to real time
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = f64::from(time_scale);
+    }
+
+    /// Change simulated time of one step, e.g. after frames per second were reconfigured
+    pub fn set_step(&mut self, step: Duration) {
+        self.step = step;
+        self.accumulator = self.accumulator.min(step);
+    }
+
+    /// Is time stopped
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stop or resume time, time which passes while paused is not simulated later
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.accumulator = Duration::ZERO;
+        self.pending_steps = 0;
+    }
+
+    /// Do a single step on next [Timestep::tick] while paused, ignored otherwise
+    pub fn request_step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    /// Number of simulation steps due since the last call
+    pub fn tick(&mut self) -> u32 {
+        let now = Instant::now();
+        let elapsed = now - self.last_tick;
+        self.last_tick = now;
+        if self.paused {
+            return std::mem::take(&mut self.pending_steps);
+        }
+        self.advance(elapsed)
+    }
+
+    /// Number of simulation steps due after `elapsed` real time, at most [MAX_CATCH_UP_STEPS]
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed.mul_f64(self.time_scale);
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+            if steps == MAX_CATCH_UP_STEPS {
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+        }
+        steps
+    }
+
+    /// Real time until the next simulation step is due, at most one step so that input is still handled
+    /// in very slow motion
+    pub fn until_next(&self) -> Duration {
+        self.step
+            .saturating_sub(self.accumulator)
+            .div_f64(self.time_scale)
+            .min(self.step)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn steps_accumulate() {
+        let mut timestep = Timestep::new(STEP, 1.0);
+        assert_eq!(timestep.advance(Duration::from_millis(4)), 0);
+        assert_eq!(timestep.until_next(), Duration::from_millis(6));
+        assert_eq!(timestep.advance(Duration::from_millis(7)), 1);
+        assert_eq!(timestep.advance(Duration::from_millis(29)), 3);
+        assert_eq!(timestep.until_next(), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn slow_rendering_drops_time() {
+        let mut timestep = Timestep::new(STEP, 1.0);
+        assert_eq!(timestep.advance(Duration::from_millis(1000)), MAX_CATCH_UP_STEPS);
+        assert_eq!(timestep.advance(Duration::from_millis(5)), 0);
+    }
+
+    #[test]
+    fn time_scale() {
+        let mut timestep = Timestep::new(STEP, 0.5);
+        assert_eq!(timestep.advance(Duration::from_millis(15)), 0);
+        assert_eq!(timestep.until_next(), Duration::from_millis(5));
+        assert_eq!(timestep.advance(Duration::from_millis(5)), 1);
+
+        let mut timestep = Timestep::new(STEP, 2.0);
+        assert_eq!(timestep.advance(Duration::from_millis(10)), 2);
+    }
+
+    #[test]
+    fn paused_does_only_requested_steps() {
+        let mut timestep = Timestep::new(STEP, 1.0);
+        timestep.request_step();
+        timestep.set_paused(true);
+        std::thread::sleep(STEP * 2);
+        assert_eq!(timestep.tick(), 0);
+        timestep.request_step();
+        timestep.request_step();
+        assert_eq!(timestep.tick(), 2);
+        assert_eq!(timestep.tick(), 0);
+    }
+}
@@ -0,0 +1,228 @@
+/// Ranges of characters which take no space on their own (combining marks, joiners, variation selectors...),
+/// sorted
+const ZERO_WIDTH: [(u32, u32); 38] = [
+    (0x0300, 0x036F),
+    (0x0483, 0x0489),
+    (0x0591, 0x05BD),
+    (0x05BF, 0x05BF),
+    (0x05C1, 0x05C2),
+    (0x05C4, 0x05C5),
+    (0x05C7, 0x05C7),
+    (0x0610, 0x061A),
+    (0x064B, 0x065F),
+    (0x0670, 0x0670),
+    (0x06D6, 0x06DC),
+    (0x06DF, 0x06E4),
+    (0x06E7, 0x06E8),
+    (0x06EA, 0x06ED),
+    (0x0900, 0x0902),
+    (0x093A, 0x093A),
+    (0x093C, 0x093C),
+    (0x0941, 0x0948),
+    (0x094D, 0x094D),
+    (0x0951, 0x0957),
+    (0x0E31, 0x0E31),
+    (0x0E34, 0x0E3A),
+    (0x0E47, 0x0E4E),
+    (0x1160, 0x11FF),
+    (0x1AB0, 0x1AFF),
+    (0x1DC0, 0x1DFF),
+    (0x200B, 0x200F),
+    (0x202A, 0x202E),
+    (0x2060, 0x2064),
+    (0x20D0, 0x20FF),
+    (0x302A, 0x302D),
+    (0x3099, 0x309A),
+    (0xFE00, 0xFE0F),
+    (0xFE20, 0xFE2F),
+    (0xFEFF, 0xFEFF),
+    (0x1F3FB, 0x1F3FF),
+    (0xE0000, 0xE007F),
+    (0xE0100, 0xE01EF),
+];
+
+/// Ranges of East Asian wide and fullwidth characters (and emoji) which take two columns in terminal, sorted
+const DOUBLE_WIDTH: [(u32, u32); 62] = [
+    (0x1100, 0x115F),
+    (0x231A, 0x231B),
+    (0x2329, 0x232A),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x2E80, 0x3029),
+    (0x302E, 0x303E),
+    (0x3041, 0x3098),
+    (0x309B, 0xA4CF),
+    (0xA960, 0xA97F),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE10, 0xFE19),
+    (0xFE30, 0xFE6F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x16FE0, 0x16FE4),
+    (0x17000, 0x18CFF),
+    (0x1B000, 0x1B2FF),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F200, 0x1F251),
+    (0x1F300, 0x1F3FA),
+    (0x1F400, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F7E0, 0x1F7EB),
+    (0x1F90C, 0x1F9FF),
+    (0x1FA70, 0x1FAFF),
+    (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+];
+
+/// Zero width joiner, joins characters around it into single grapheme (e.g. emoji sequences)
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Regional indicator symbols, pair of them is a flag which takes two columns
+const REGIONAL_INDICATORS: std::ops::RangeInclusive<char> = '\u{1F1E6}'..='\u{1F1FF}';
+
+/// Is `ch` in one of sorted `ranges`
+fn in_ranges(ch: char, ranges: &[(u32, u32)]) -> bool {
+    let ch = u32::from(ch);
+    ranges
+        .binary_search_by(|&(first, last)| {
+            if last < ch {
+                std::cmp::Ordering::Less
+            } else if first > ch {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Number of terminal columns taken by `ch`: 0 for combining marks and control characters,
+/// 2 for wide characters like CJK or full-width katakana, 1 otherwise
+pub fn char_width(ch: char) -> usize {
+    if ch.is_control() || in_ranges(ch, &ZERO_WIDTH) {
+        0
+    } else if in_ranges(ch, &DOUBLE_WIDTH) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Split `text` into graphemes: characters together with combining marks and joined characters which follow them,
+/// pairs of regional indicators form flags
+///
+/// This is simplified version of Unicode grapheme clusters, good enough to know what takes terminal columns
+pub fn graphemes(text: &str) -> Vec<&str> {
+    let mut graphemes = Vec::new();
+    let mut start = 0;
+    let mut joined = false;
+    // regional indicator which starts a flag and waits for its second half
+    let mut flag_started = false;
+    for (index, ch) in text.char_indices() {
+        let flag_ends = flag_started && REGIONAL_INDICATORS.contains(&ch);
+        if index > start && char_width(ch) > 0 && !joined && !flag_ends {
+            graphemes.push(&text[start..index]);
+            start = index;
+        }
+        joined = ch == ZERO_WIDTH_JOINER;
+        flag_started = REGIONAL_INDICATORS.contains(&ch) && !flag_ends;
+    }
+    if start < text.len() {
+        graphemes.push(&text[start..]);
+    }
+    graphemes
+}
+
+/// Number of terminal columns taken by single `grapheme`, at least 1
+pub fn grapheme_width(grapheme: &str) -> usize {
+    let width = grapheme.chars().next().map_or(1, char_width);
+    let flag = grapheme.chars().filter(|ch| REGIONAL_INDICATORS.contains(ch)).count() == 2;
+    // variation selector 16 asks for emoji presentation, which is wide
+    if flag || grapheme.contains('\u{FE0F}') {
+        2
+    } else {
+        width.clamp(1, 2)
+    }
+}
+
+/// Number of terminal columns taken by `text`
+pub fn width(text: &str) -> usize {
+    graphemes(text).into_iter().map(grapheme_width).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sorted_ranges() {
+        for ranges in [&ZERO_WIDTH[..], &DOUBLE_WIDTH[..]] {
+            assert!(ranges.iter().all(|(first, last)| first <= last));
+            assert!(ranges.windows(2).all(|pair| pair[0].1 < pair[1].0));
+        }
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('ｱ'), 1);
+        assert_eq!(char_width('ア'), 2);
+        assert_eq!(char_width('漢'), 2);
+        assert_eq!(char_width('\u{301}'), 0);
+        assert_eq!(width("Lorem"), 5);
+        assert_eq!(width("ｦｱｳ"), 3);
+        assert_eq!(width("マトリックス"), 12);
+        assert_eq!(width("e\u{301}t\u{e9}"), 3);
+    }
+
+    #[test]
+    fn split_graphemes() {
+        assert_eq!(graphemes("ab"), ["a", "b"]);
+        assert_eq!(graphemes("e\u{301}ア"), ["e\u{301}", "ア"]);
+        assert_eq!(graphemes("👩\u{200D}💻x"), ["👩\u{200D}💻", "x"]);
+        assert_eq!(graphemes(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn flags() {
+        // 🇵🇱🇯🇵 and single 🇺
+        let text = "\u{1F1F5}\u{1F1F1}\u{1F1EF}\u{1F1F5}\u{1F1FA}";
+        assert_eq!(graphemes(text), ["\u{1F1F5}\u{1F1F1}", "\u{1F1EF}\u{1F1F5}", "\u{1F1FA}"]);
+        assert_eq!(grapheme_width("\u{1F1F5}\u{1F1F1}"), 2);
+        assert_eq!(grapheme_width("\u{1F1FA}"), 1);
+        assert_eq!(width(text), 5);
+    }
+}
@@ -0,0 +1,311 @@
+use crate::backend::{Backend, MemoryBackend};
+use crate::colors::Color;
+use crate::error::Error;
+use crate::frame_buffer::Style;
+use crate::headless::FrameExporter;
+use crate::position::{Position, PositionTrait};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// Width of single cell in SVG units
+const CELL_WIDTH: u32 = 10;
+/// Height of single cell in SVG units
+const CELL_HEIGHT: u32 = 18;
+/// Font size which makes monospace characters fit in [CELL_WIDTH] x [CELL_HEIGHT] cell
+const FONT_SIZE: u32 = 16;
+/// Color of cells which use terminal default color
+const DEFAULT_FOREGROUND: &str = "#cccccc";
+/// Color of background
+const BACKGROUND: &str = "#000000";
+
+/// Format of file written by [WebExporter]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WebFormat {
+    /// Animated SVG image
+    Svg,
+    /// Self-contained HTML page with embedded animated SVG image
+    Html,
+}
+
+/// Exports frames as self-contained animated SVG image or HTML page which replays them in a loop
+///
+/// Animation uses only CSS, so it plays without any scripts. Frames are kept in memory and whole file is written on
+/// [FrameExporter::finish], because total duration of animation is needed for each frame.
+pub struct WebExporter<W: Write> {
+    /// Where the file is written
+    out: W,
+    /// [WebFormat] of written file
+    format: WebFormat,
+    /// Duration of single frame in milliseconds
+    frame_duration_ms: u64,
+    /// Size of frames in cells
+    size: Position,
+    /// SVG elements of each frame
+    frames: Vec<String>,
+}
+
+impl WebExporter<BufWriter<File>> {
+    /// Create file in `path` for frames displayed every `frame_duration_ms`
+    pub fn create(path: &str, format: WebFormat, frame_duration_ms: u64) -> Result<Self, Error> {
+        Ok(Self::new(
+            BufWriter::new(File::create(path)?),
+            format,
+            frame_duration_ms,
+        ))
+    }
+}
+
+impl<W: Write> WebExporter<W> {
+    /// Create new [WebExporter] writing to `out`
+    pub fn new(out: W, format: WebFormat, frame_duration_ms: u64) -> Self {
+        Self {
+            out,
+            format,
+            frame_duration_ms,
+            size: Position::new(0, 0),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Get reference to underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    /// Animated SVG image with all frames
+    fn svg(&self) -> String {
+        let width = u32::from(self.size.x()) * CELL_WIDTH;
+        let height = u32::from(self.size.y()) * CELL_HEIGHT;
+        let frames = self.frames.len().max(1) as u64;
+        let total = (self.frame_duration_ms * frames) as f64 / 1000.0;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        );
+        let _ = writeln!(
+            svg,
+            "<style>\
+             text{{font-family:monospace;font-size:{FONT_SIZE}px;white-space:pre}}\
+             .f{{visibility:hidden;animation:frame {total}s step-end infinite}}\
+             @keyframes frame{{0%{{visibility:visible}}{}%{{visibility:hidden}}}}\
+             </style>",
+            100.0 / frames as f64
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="100%" height="100%" fill="{BACKGROUND}"/>"#
+        );
+        for (frame_no, frame) in self.frames.iter().enumerate() {
+            let delay = (self.frame_duration_ms * frame_no as u64) as f64 / 1000.0;
+            let _ = writeln!(
+                svg,
+                r#"<g class="f" style="animation-delay:{delay}s">{frame}</g>"#
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// CSS color of `style`
+fn css_color(style: &Style) -> String {
+    match style.color {
+        Some(ref color) => css_rgb(color),
+        None => DEFAULT_FOREGROUND.to_owned(),
+    }
+}
+
+/// CSS `#rrggbb` notation of `color`
+fn css_rgb(color: &Color) -> String {
+    let (r, g, b) = color.to_rgb();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// SVG rectangles painting backgrounds of cells in row `y` which have one
+fn background_elements(backend: &MemoryBackend, y: u16) -> String {
+    let mut elements = String::new();
+    let mut runs: Vec<(&Color, u16, u16)> = Vec::new();
+    for x in 1..=backend.size().x() {
+        let Some(background) = backend
+            .cell(&Position::new(x, y))
+            .and_then(|cell| cell.style.background.as_ref())
+        else {
+            continue;
+        };
+        match runs.last_mut() {
+            Some((color, _, last)) if *color == background && *last == x - 1 => *last = x,
+            _ => runs.push((background, x, x)),
+        }
+    }
+    for (color, first, last) in runs {
+        let _ = write!(
+            elements,
+            r#"<rect x="{}" y="{}" width="{}" height="{CELL_HEIGHT}" fill="{}"/>"#,
+            u32::from(first - 1) * CELL_WIDTH,
+            u32::from(y - 1) * CELL_HEIGHT,
+            u32::from(last - first + 1) * CELL_WIDTH,
+            css_rgb(color)
+        );
+    }
+    elements
+}
+
+/// Escape characters which have special meaning in XML
+fn escape(ch: char) -> String {
+    match ch {
+        '&' => "&amp;".to_owned(),
+        '<' => "&lt;".to_owned(),
+        '>' => "&gt;".to_owned(),
+        _ => ch.to_string(),
+    }
+}
+
+/// SVG elements of current contents of `backend`, one `<text>` per row with one `<tspan>` per run of the same [Style]
+///
+/// Every character gets its own x coordinate, so the grid does not depend on the font used by the viewer
+fn frame_elements(backend: &MemoryBackend) -> String {
+    let size = backend.size();
+    let mut elements = String::new();
+    for y in 1..=size.y() {
+        elements.push_str(&background_elements(backend, y));
+        let mut runs: Vec<(&Style, Vec<u16>, String, bool)> = Vec::new();
+        for x in 1..=size.x() {
+            let Some(cell) = backend.cell(&Position::new(x, y)) else {
+                continue;
+            };
+            if cell.symbol == " " || cell.is_continuation() {
+                continue;
+            }
+            let symbol: String = cell.symbol.chars().map(escape).collect();
+            // x is set for each character, so symbols with combining characters get their own run
+            let single = cell.symbol.chars().nth(1).is_none();
+            match runs.last_mut() {
+                Some((style, columns, text, true))
+                    if single && **style == cell.style && columns.last() == Some(&(x - 1)) =>
+                {
+                    columns.push(x);
+                    text.push_str(&symbol);
+                }
+                _ => runs.push((&cell.style, vec![x], symbol, single)),
+            }
+        }
+        if runs.is_empty() {
+            continue;
+        }
+        // baseline a bit above the bottom of the cell
+        let baseline = u32::from(y) * CELL_HEIGHT - CELL_HEIGHT / 4;
+        let _ = write!(elements, r#"<text y="{baseline}">"#);
+        for (style, columns, text, _) in runs {
+            let xs: Vec<String> = columns
+                .iter()
+                .map(|x| (u32::from(x - 1) * CELL_WIDTH).to_string())
+                .collect();
+            let weight = if style.bold {
+                r#" font-weight="bold""#
+            } else {
+                ""
+            };
+            let _ = write!(
+                elements,
+                r#"<tspan x="{}" fill="{}"{weight}>{text}</tspan>"#,
+                xs.join(" "),
+                css_color(style)
+            );
+        }
+        elements.push_str("</text>");
+    }
+    elements
+}
+
+impl<W: Write> FrameExporter for WebExporter<W> {
+    fn add_frame(&mut self, backend: &MemoryBackend) -> Result<(), Error> {
+        self.size = backend.size();
+        self.frames.push(frame_elements(backend));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let svg = self.svg();
+        match self.format {
+            WebFormat::Svg => self.out.write_all(svg.as_bytes())?,
+            WebFormat::Html => write!(
+                self.out,
+                "<!DOCTYPE html>\n\
+                 <html>\n\
+                 <head><meta charset=\"utf-8\"><title>ruMatrix</title></head>\n\
+                 <body style=\"margin:0;background:{BACKGROUND}\">\n\
+                 {svg}\
+                 </body>\n\
+                 </html>\n"
+            )?,
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn backend() -> MemoryBackend {
+        let mut backend = MemoryBackend::new(Position::new(4, 2));
+        backend
+            .set_style(&Style {
+                color: Some(Color::RGB {
+                    r: 255,
+                    g: 0,
+                    b: 16,
+                }),
+                bold: true,
+                background: None,
+            })
+            .unwrap();
+        backend.put_cell(&Position::new(2, 1), "<").unwrap();
+        backend.put_cell(&Position::new(3, 1), "a").unwrap();
+        backend
+    }
+
+    #[test]
+    fn frame_elements_use_grid_and_css_colors() {
+        assert_eq!(
+            frame_elements(&backend()),
+            r##"<text y="14"><tspan x="10 20" fill="#ff0010" font-weight="bold">&lt;a</tspan></text>"##
+        );
+    }
+
+    #[test]
+    fn backgrounds_are_drawn_as_rectangles() {
+        let mut backend = MemoryBackend::new(Position::new(4, 2));
+        backend
+            .set_style(&Style {
+                color: None,
+                bold: false,
+                background: Some(Color::Palette(4)),
+            })
+            .unwrap();
+        backend.put_cell(&Position::new(2, 2), " ").unwrap();
+        backend.put_cell(&Position::new(3, 2), " ").unwrap();
+        assert_eq!(
+            frame_elements(&backend),
+            r##"<rect x="10" y="18" width="20" height="18" fill="#0000ee"/>"##
+        );
+    }
+
+    #[test]
+    fn html_contains_every_frame() {
+        let mut exporter = WebExporter::new(Vec::new(), WebFormat::Html, 100);
+        exporter.add_frame(&backend()).unwrap();
+        exporter.add_frame(&backend()).unwrap();
+        exporter.finish().unwrap();
+        let html = String::from_utf8(exporter.get_ref().clone()).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(r#"width="40" height="36""#));
+        assert!(html.contains("animation:frame 0.2s"));
+        assert!(html.contains(r#"style="animation-delay:0s""#));
+        assert!(html.contains(r#"style="animation-delay:0.1s""#));
+        assert_eq!(html.matches("<g ").count(), 2);
+    }
+}